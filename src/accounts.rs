@@ -0,0 +1,2635 @@
+//! Account validation for every instruction
+use solana_program::{
+    account_info::{AccountInfo,next_account_info},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg,
+};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta,seeds::Seed,state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address,
+    get_extra_account_metas_address_and_bump_seed,
+    instruction::{ExecuteInstruction,TransferHookInstruction},
+};
+use crate::{
+    error::TokenError,
+    quote::Quote,
+    royalties::Royalties,
+    state::{ProgramAccount,NftDetails,CoinFlip,Auction,Config,Proceeds,StakePool,StakeEntry,Proposal,Vote,Buyout,BuyoutStatus,HolderRecord,Purchase},
+    utils::{
+        assert_signer,
+        assert_writable,
+        assert_owned_by,
+        assert_token_program,
+        assert_supported_token_program,
+        assert_associated_token_program,
+        assert_system_program,
+        assert_rent_sysvar,
+        assert_pda,
+        assert_associated_token,
+        generate_auction_pda,
+        generate_pda_and_bump_seed,
+        get_upgrade_authority,
+        get_mint_authority,
+        get_transfer_hook_program,
+        assert_transfer_hook,
+        assert_transferring,
+    },
+    SPLTOKENPREFIX,
+    NFTPREFIX,
+    CONFIGPREFIX,
+    PROCEEDSPREFIX,
+    STAKEPREFIX,
+    STAKEENTRYPREFIX,
+    VOTEPREFIX,
+    BUYOUTPREFIX,
+    HOLDERPREFIX,
+    PURCHASEPREFIX,
+};
+
+/// Checks the config pda and the quote account of the treasury it points to, both trail the payout instructions
+fn validate_fee_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    config_info: &'a AccountInfo<'b>,
+    treasury: &'a AccountInfo<'b>,
+    quote: &Quote,
+) -> Result<Config, ProgramError> {
+    let (config_address, _bump) = Pubkey::find_program_address(&[CONFIGPREFIX.as_bytes()], program_id);
+    if config_address != *config_info.key
+    {
+        return Err(TokenError::InvalidConfig.into());
+    }
+    assert_writable(config_info)?;
+    let config = Config::load(config_info, program_id)?;
+    if quote.account_of(&config.treasury) != *treasury.key
+    {
+        msg!("Treasury doesn't match the config");
+        return Err(TokenError::InvalidTreasury.into());
+    }
+    assert_writable(treasury)?;
+    Ok(config)
+}
+
+/// Checks the proceeds pda of the vault, it is only loaded when the vault splits its proceeds
+fn validate_proceeds_account<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    proceeds_info: &'a AccountInfo<'b>,
+    nft_owner: &'a AccountInfo<'b>,
+    pda: &'a AccountInfo<'b>,
+    escrow: &NftDetails,
+) -> Result<Option<Proceeds>, ProgramError> {
+    assert_pda(proceeds_info, PROCEEDSPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidProceeds)?;
+    if !escrow.split_proceeds
+    {
+        return Ok(None);
+    }
+    assert_writable(proceeds_info)?;
+    let proceeds = Proceeds::load(proceeds_info, program_id)?;
+    if proceeds.vault != *pda.key
+    {
+        return Err(TokenError::InvalidProceeds.into());
+    }
+    Ok(Some(proceeds))
+}
+
+/// Loads the auction of a vault, auctions of the other vaults of the curator are rejected
+fn load_auction(auction_data: &AccountInfo, pda: &AccountInfo, program_id: &Pubkey) -> Result<Auction, ProgramError> {
+    let auction = Auction::load(auction_data, program_id)?;
+    if auction.vault != *pda.key
+    {
+        msg!("The auction belongs to another vault");
+        return Err(TokenError::InvalidAuction.into());
+    }
+    Ok(auction)
+}
+
+/// Accounts of [ProcessDeposit](../instruction/struct.ProcessDeposit.html)
+pub struct DepositAccounts<'a, 'b: 'a> {
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    /// Token program of the quote mint, it can differ from the token program of the fractions
+    pub quote_token_program: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub spl_associated_token: &'a AccountInfo<'b>,
+    pub nft_mint: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub nft_associated_address: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub nft_spl_owner_address: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub nft_owner_nft_associated: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// Proceeds pda from PROCEEDSPREFIX, only required when depositing with beneficiaries
+    pub proceeds_info: Option<&'a AccountInfo<'b>>,
+    /// Quote mint of the vault, only required for an SPL quote mint
+    pub quote_mint_info: Option<&'a AccountInfo<'b>>,
+    /// Associated address from nft vault and quote mint
+    pub vault_quote: Option<&'a AccountInfo<'b>>,
+    /// Associated address from proceeds pda and quote mint, only required with beneficiaries
+    pub proceeds_quote: Option<&'a AccountInfo<'b>>,
+    /// Token program of the nft mint, token_program_id when the nft is minted under the same program
+    pub nft_token_program: &'a AccountInfo<'b>,
+    /// Transfer hook program of the fraction mint, only required with a transfer hook
+    pub transfer_hook_program: Option<&'a AccountInfo<'b>>,
+    /// Validation pda of the fraction mint under the transfer hook program, only required with a transfer hook
+    pub extra_account_metas: Option<&'a AccountInfo<'b>>,
+    pub nft_vault_bump: u8,
+    pub spl_token_bump: u8,
+    pub proceeds_bump: u8,
+}
+impl<'a, 'b: 'a> DepositAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], quote_mint: &Pubkey, transfer_hook: Option<&Pubkey>) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let nft_owner =  next_account_info(account_info_iter)?; // sender or signer
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let pda = next_account_info(account_info_iter)?; // pda data
+        let spl_token_mint = next_account_info(account_info_iter)?;  // spl token address generated from SPLTOKENPREFIX, nft_owner, pda and program id
+        let spl_associated_token = next_account_info(account_info_iter)?; // nft owner associated of spl_token_mint
+        let nft_mint = next_account_info(account_info_iter)?;  // mint address of nft
+        let nft_vault = next_account_info(account_info_iter)?;  // nft vault address from NFTPREFIX, nft_owner, pda and program id
+        let nft_associated_address = next_account_info(account_info_iter)?; // address generated from nft_vault_address and nft mint address token account address
+        let spl_vault_associated_address = next_account_info(account_info_iter)?; // address generated from nft_vault_address and spl token mint address
+        let nft_spl_owner_address = next_account_info(account_info_iter)?; // // nft/token vault address generated from spltoken mint, nft_owner, pda and program id
+        let associated_token_info = next_account_info(account_info_iter)?; // Associated token master {ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL}
+        let nft_owner_nft_associated = next_account_info(account_info_iter)?;  // nft owner nft id token account address
+        let rent_info  = next_account_info(account_info_iter)?; // rent
+        let system_program = next_account_info(account_info_iter)?; //system program
+        let proceeds_info = next_account_info(account_info_iter).ok(); // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let quote_mint_info = next_account_info(account_info_iter).ok(); // quote mint
+        let vault_quote = next_account_info(account_info_iter).ok(); // associated address from nft vault and quote mint
+        let proceeds_quote = next_account_info(account_info_iter).ok(); // associated address from proceeds pda and quote mint
+        let nft_token_program = next_account_info(account_info_iter).unwrap_or(token_program_id); // token program of the nft mint
+        let transfer_hook_program = next_account_info(account_info_iter).ok(); // transfer hook program of the spl token mint
+        let extra_account_metas = next_account_info(account_info_iter).ok(); // validation pda from spl token mint and transfer hook program
+
+        assert_signer(nft_owner)?;
+        assert_writable(nft_owner)?;
+        assert_supported_token_program(token_program_id)?;
+        assert_supported_token_program(nft_token_program)?;
+        assert_supported_token_program(quote_token_program)?;
+        //the data account is created by the nft owner so it has to sign as well
+        assert_signer(pda)?;
+        assert_writable(pda)?;
+        let spl_token_bump = assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_writable(spl_token_mint)?;
+        assert_associated_token(spl_associated_token, nft_owner.key, spl_token_mint.key, token_program_id.key)?;
+        assert_owned_by(nft_mint, nft_token_program.key)?;
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_writable(nft_vault)?;
+        assert_associated_token(nft_associated_address, nft_vault.key, nft_mint.key, nft_token_program.key)?;
+        assert_writable(nft_associated_address)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, token_program_id.key)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_associated_token(nft_owner_nft_associated, nft_owner.key, nft_mint.key, nft_token_program.key)?;
+        assert_writable(nft_owner_nft_associated)?;
+        assert_rent_sysvar(rent_info)?;
+        assert_system_program(system_program)?;
+        let mut proceeds_bump = 0;
+        if let Some(proceeds_info) = proceeds_info {
+            proceeds_bump = assert_pda(proceeds_info, PROCEEDSPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidProceeds)?;
+            assert_writable(proceeds_info)?;
+        }
+        if *quote_mint != Pubkey::default()
+        {
+            let quote_mint_info = quote_mint_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if quote_mint_info.key != quote_mint
+            {
+                return Err(TokenError::InvalidMint.into());
+            }
+            assert_owned_by(quote_mint_info, quote_token_program.key)?;
+            let vault_quote = vault_quote.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            assert_associated_token(vault_quote, nft_vault.key, quote_mint, quote_token_program.key)?;
+            assert_writable(vault_quote)?;
+            if let (Some(proceeds_info), Some(proceeds_quote)) = (proceeds_info, proceeds_quote) {
+                assert_associated_token(proceeds_quote, proceeds_info.key, quote_mint, quote_token_program.key)?;
+                assert_writable(proceeds_quote)?;
+            }
+        }
+        if let Some(transfer_hook) = transfer_hook {
+            let transfer_hook_program = transfer_hook_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if transfer_hook_program.key != transfer_hook || !transfer_hook_program.executable
+            {
+                return Err(TokenError::InvalidTransferHook.into());
+            }
+            //token-2022 calls the hook within the transfers of this program, which can't reenter itself
+            if transfer_hook == program_id
+            {
+                msg!("The transfer hook must be a separate deployment of the program");
+                return Err(TokenError::InvalidTransferHook.into());
+            }
+            let extra_account_metas = extra_account_metas.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *extra_account_metas.key != get_extra_account_metas_address(spl_token_mint.key, transfer_hook)
+            {
+                return Err(TokenError::InvalidTransferHook.into());
+            }
+            assert_writable(extra_account_metas)?;
+        }
+
+        Ok(Self{
+            nft_owner,
+            token_program_id,
+            quote_token_program,
+            pda,
+            spl_token_mint,
+            spl_associated_token,
+            nft_mint,
+            nft_vault,
+            nft_associated_address,
+            spl_vault_associated_address,
+            nft_spl_owner_address,
+            associated_token_info,
+            nft_owner_nft_associated,
+            rent_info,
+            system_program,
+            proceeds_info,
+            quote_mint_info,
+            vault_quote,
+            proceeds_quote,
+            nft_token_program,
+            transfer_hook_program,
+            extra_account_metas,
+            nft_vault_bump,
+            spl_token_bump,
+            proceeds_bump,
+        })
+    }
+}
+
+/// Accounts of [ProcessAuction1](../instruction/struct.ProcessAuction1.html)
+pub struct AuctionAccounts<'a, 'b: 'a> {
+    pub bidder: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda_data: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub auction_data: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub bidder_quote: &'a AccountInfo<'b>,
+    pub vault_quote: &'a AccountInfo<'b>,
+    /// Previous highest bidder, only required when outbidding
+    pub max_payer: Option<&'a AccountInfo<'b>>,
+    /// Quote account of the previous highest bidder, receives the refund
+    pub max_payer_quote: Option<&'a AccountInfo<'b>>,
+    pub quote: Quote<'a, 'b>,
+    pub escrow: NftDetails,
+    /// Existing auction of the day, None if the account is still empty
+    pub auction: Option<Auction>,
+    pub day: u64,
+    pub nft_vault_bump: u8,
+    pub auction_bump: u8,
+}
+impl<'a, 'b: 'a> AuctionAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], now: u64) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let bidder =  next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda_data = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault which saves the amount
+        let auction_data = next_account_info(account_info_iter)?; //account made using Auction Prefix, Nft owner, pda and Day
+        let system_program = next_account_info(account_info_iter)?;//system_program
+        let rent_info  = next_account_info(account_info_iter)?; // rent
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let bidder_quote = next_account_info(account_info_iter)?; // quote account of the bidder, the bidder itself for SOL
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault, escrows the bids
+        let max_payer = next_account_info(account_info_iter).ok(); // previous maximum payer obtained by deserializing auction_data
+        let max_payer_quote = next_account_info(account_info_iter).ok(); // quote account of the previous maximum payer
+
+        assert_signer(bidder)?;
+        assert_writable(bidder)?;
+        assert_writable(pda_data)?;
+        let escrow = NftDetails::load(pda_data, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            msg!("NFT owner doesn't match");
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda_data.key, program_id, TokenError::InvalidVault)?;
+        if escrow.nft_escrow != *nft_vault.key
+        {
+            msg!("NFT vault doesn't match");
+            return Err(TokenError::InvalidVault.into());
+        }
+        assert_writable(nft_vault)?;
+        let day = escrow.day_at(now)?;
+        let (auction_address, auction_bump) = generate_auction_pda(nft_owner.key, pda_data.key, day, program_id);
+        if auction_address != *auction_data.key
+        {
+            msg!("auction address don't match {}",auction_address);
+            return Err(TokenError::InvalidAuction.into());
+        }
+        assert_writable(auction_data)?;
+        let auction = if auction_data.data_is_empty() {
+            None
+        } else {
+            Some(load_auction(auction_data, pda_data, program_id)?)
+        };
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(bidder_quote, bidder.key)?;
+        quote.assert_account(vault_quote, nft_vault.key)?;
+        if let (Some(max_payer), Some(auction)) = (max_payer, &auction) {
+            if *max_payer.key != auction.max_payer
+            {
+                msg!("Previous highest bidder doesn't match");
+                return Err(TokenError::InvalidPreviousBidder.into());
+            }
+            assert_writable(max_payer)?;
+            if let Some(max_payer_quote) = max_payer_quote {
+                quote.assert_account(max_payer_quote, max_payer.key)?;
+            }
+        }
+
+        Ok(Self{
+            bidder,
+            nft_owner,
+            pda_data,
+            nft_vault,
+            auction_data,
+            system_program,
+            rent_info,
+            bidder_quote,
+            vault_quote,
+            max_payer,
+            max_payer_quote,
+            quote,
+            escrow,
+            auction,
+            day,
+            nft_vault_bump,
+            auction_bump,
+        })
+    }
+}
+
+/// Accounts of [ProcessBuy](../instruction/struct.ProcessBuy.html)
+pub struct BuyAccounts<'a, 'b: 'a> {
+    pub buyer: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda_data: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub buyer_spl_associated: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub treasury: &'a AccountInfo<'b>,
+    pub proceeds_info: &'a AccountInfo<'b>,
+    pub buyer_quote: &'a AccountInfo<'b>,
+    pub owner_quote: &'a AccountInfo<'b>,
+    pub proceeds_quote: &'a AccountInfo<'b>,
+    /// Quote account of the nft vault, it escrows the premiums of a refundable dutch sale and the sale reserve
+    pub vault_quote: &'a AccountInfo<'b>,
+    /// Purchase pda from PURCHASEPREFIX, buyer and pda, only used by capped and refundable sales
+    pub purchase_info: &'a AccountInfo<'b>,
+    pub metadata: &'a AccountInfo<'b>,
+    /// Trailing accounts of the verified creators of the nft
+    pub creators: &'a [AccountInfo<'b>],
+    pub escrow: NftDetails,
+    pub config: Config,
+    /// Beneficiaries of the vault, None if the nft owner is paid directly
+    pub proceeds: Option<Proceeds>,
+    pub royalties: Royalties,
+    pub quote: Quote<'a, 'b>,
+    /// Purchase of the buyer, None if the account is still empty
+    pub purchase: Option<Purchase>,
+    pub nft_vault_bump: u8,
+    pub purchase_bump: u8,
+}
+impl<'a, 'b: 'a> BuyAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let buyer =  next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda_data = next_account_info(account_info_iter)?; // pda data that consists number of tokens
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
+        let buyer_spl_associated =  next_account_info(account_info_iter)?; // sender or signer
+        let spl_token_mint = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?; // protocol config pda from CONFIGPREFIX
+        let treasury = next_account_info(account_info_iter)?; // protocol treasury set in the config
+        let proceeds_info = next_account_info(account_info_iter)?; // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let buyer_quote = next_account_info(account_info_iter)?; // quote account of the buyer, the buyer itself for SOL
+        let owner_quote = next_account_info(account_info_iter)?; // quote account of the nft owner
+        let proceeds_quote = next_account_info(account_info_iter)?; // quote account of the proceeds pda
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault
+        let purchase_info = next_account_info(account_info_iter)?; // purchase pda from PURCHASEPREFIX, buyer and pda
+        let metadata = next_account_info(account_info_iter)?; // metaplex metadata of the nft
+        let creators = account_info_iter.as_slice(); // verified creators of the nft metadata
+
+        assert_signer(buyer)?;
+        assert_writable(buyer)?;
+        assert_writable(pda_data)?;
+        let escrow = NftDetails::load(pda_data, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(nft_owner)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda_data.key, program_id, TokenError::InvalidVault)?;
+        if escrow.nft_escrow != *nft_vault.key
+        {
+            return Err(TokenError::InvalidVault.into());
+        }
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda_data.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_associated_token(buyer_spl_associated, buyer.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(buyer_spl_associated)?;
+        assert_rent_sysvar(rent_info)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        let config = validate_fee_accounts(program_id, config_info, treasury, &quote)?;
+        let proceeds = validate_proceeds_account(program_id, proceeds_info, nft_owner, pda_data, &escrow)?;
+        quote.assert_account(buyer_quote, buyer.key)?;
+        quote.assert_account(owner_quote, nft_owner.key)?;
+        if proceeds.is_some()
+        {
+            quote.assert_account(proceeds_quote, proceeds_info.key)?;
+        }
+        if escrow.sale_refunds || escrow.escrows_sale()
+        {
+            quote.assert_account(vault_quote, nft_vault.key)?;
+        }
+        let royalties = Royalties::load(metadata, &escrow.nft_mint)?;
+        let purchase_bump = assert_pda(purchase_info, PURCHASEPREFIX, buyer.key, pda_data.key, program_id, TokenError::InvalidPurchase)?;
+        let purchase = if purchase_info.data_is_empty() {
+            None
+        } else {
+            assert_writable(purchase_info)?;
+            Some(Purchase::load(purchase_info, program_id)?)
+        };
+
+        Ok(Self{
+            buyer,
+            nft_owner,
+            pda_data,
+            token_program_id,
+            nft_vault,
+            spl_vault_associated_address,
+            buyer_spl_associated,
+            spl_token_mint,
+            rent_info,
+            associated_token_info,
+            system_program,
+            config_info,
+            treasury,
+            proceeds_info,
+            buyer_quote,
+            owner_quote,
+            proceeds_quote,
+            vault_quote,
+            purchase_info,
+            metadata,
+            creators,
+            escrow,
+            config,
+            proceeds,
+            royalties,
+            quote,
+            purchase,
+            nft_vault_bump,
+            purchase_bump,
+        })
+    }
+}
+
+/// Accounts of [ProcessBuy2](../instruction/struct.ProcessBuy2.html)
+pub struct SettleAccounts<'a, 'b: 'a> {
+    pub buyer: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda_data: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub buyer_spl_associated: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub auction_data: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub treasury: &'a AccountInfo<'b>,
+    pub proceeds_info: &'a AccountInfo<'b>,
+    pub stake_pool_info: &'a AccountInfo<'b>,
+    pub vault_quote: &'a AccountInfo<'b>,
+    pub owner_quote: &'a AccountInfo<'b>,
+    pub proceeds_quote: &'a AccountInfo<'b>,
+    pub stake_pool_quote: &'a AccountInfo<'b>,
+    pub metadata: &'a AccountInfo<'b>,
+    /// Trailing accounts of the verified creators of the nft
+    pub creators: &'a [AccountInfo<'b>],
+    pub escrow: NftDetails,
+    pub auction: Auction,
+    pub config: Config,
+    /// Beneficiaries of the vault, None if the nft owner is paid directly
+    pub proceeds: Option<Proceeds>,
+    /// Stake pool of the vault, None if it hasn't been created
+    pub stake_pool: Option<StakePool>,
+    pub royalties: Royalties,
+    pub quote: Quote<'a, 'b>,
+    pub nft_vault_bump: u8,
+    pub spl_token_bump: u8,
+}
+impl<'a, 'b: 'a> SettleAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], day: u64) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let buyer =  next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda_data = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
+        let buyer_spl_associated =  next_account_info(account_info_iter)?; // sender or signer
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info =next_account_info(account_info_iter)?;
+        let auction_data=next_account_info(account_info_iter)?;
+        let associated_token_info= next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?; // protocol config pda from CONFIGPREFIX
+        let treasury = next_account_info(account_info_iter)?; // protocol treasury set in the config
+        let proceeds_info = next_account_info(account_info_iter)?; // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let stake_pool_info = next_account_info(account_info_iter)?; // stake pool pda from STAKEPREFIX, nft_owner and pda
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault, the vault itself for SOL
+        let owner_quote = next_account_info(account_info_iter)?; // quote account of the nft owner
+        let proceeds_quote = next_account_info(account_info_iter)?; // quote account of the proceeds pda
+        let stake_pool_quote = next_account_info(account_info_iter)?; // quote account of the stake pool
+        let metadata = next_account_info(account_info_iter)?; // metaplex metadata of the nft
+        let creators = account_info_iter.as_slice(); // verified creators of the nft metadata
+
+        assert_signer(buyer)?;
+        assert_writable(buyer)?;
+        assert_writable(pda_data)?;
+        let escrow = NftDetails::load(pda_data, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(nft_owner)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda_data.key, program_id, TokenError::InvalidVault)?;
+        if escrow.nft_escrow != *nft_vault.key
+        {
+            return Err(TokenError::InvalidVault.into());
+        }
+        assert_writable(nft_vault)?;
+        let spl_token_bump = assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda_data.key, program_id, TokenError::InvalidMint)?;
+        assert_writable(spl_token_mint)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_associated_token(buyer_spl_associated, buyer.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(buyer_spl_associated)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+        let (auction_address, _auction_bump) = generate_auction_pda(nft_owner.key, pda_data.key, day, program_id);
+        if auction_address != *auction_data.key
+        {
+            return Err(TokenError::InvalidAuction.into());
+        }
+        if auction_data.data_is_empty()
+        {
+            msg!("The auction data is empty");
+            return Err(TokenError::Notstarted.into());
+        }
+        assert_writable(auction_data)?;
+        let auction = load_auction(auction_data, pda_data, program_id)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        let config = validate_fee_accounts(program_id, config_info, treasury, &quote)?;
+        let proceeds = validate_proceeds_account(program_id, proceeds_info, nft_owner, pda_data, &escrow)?;
+        assert_pda(stake_pool_info, STAKEPREFIX, nft_owner.key, pda_data.key, program_id, TokenError::InvalidStakePool)?;
+        let stake_pool = if stake_pool_info.data_is_empty() {
+            None
+        } else {
+            assert_writable(stake_pool_info)?;
+            Some(StakePool::load(stake_pool_info, program_id)?)
+        };
+        quote.assert_account(vault_quote, nft_vault.key)?;
+        quote.assert_account(owner_quote, nft_owner.key)?;
+        if proceeds.is_some()
+        {
+            quote.assert_account(proceeds_quote, proceeds_info.key)?;
+        }
+        if stake_pool.is_some()
+        {
+            quote.assert_account(stake_pool_quote, stake_pool_info.key)?;
+        }
+        let royalties = Royalties::load(metadata, &escrow.nft_mint)?;
+
+        Ok(Self{
+            buyer,
+            nft_owner,
+            pda_data,
+            token_program_id,
+            nft_vault,
+            spl_vault_associated_address,
+            buyer_spl_associated,
+            spl_token_mint,
+            system_program,
+            rent_info,
+            auction_data,
+            associated_token_info,
+            config_info,
+            treasury,
+            proceeds_info,
+            stake_pool_info,
+            vault_quote,
+            owner_quote,
+            proceeds_quote,
+            stake_pool_quote,
+            metadata,
+            creators,
+            escrow,
+            auction,
+            config,
+            proceeds,
+            stake_pool,
+            royalties,
+            quote,
+            nft_vault_bump,
+            spl_token_bump,
+        })
+    }
+}
+
+/// Proof that a player held the fractions, required when the vault has a minimum holding period
+pub enum HoldingProof {
+    /// Holder record kept by the transfer hook of a Token-2022 fraction mint
+    Holder(Box<HolderRecord>),
+    /// Fractions time-locked in the stake pool of the vault
+    Stake(StakeEntry),
+}
+/// Accounts of ProcessCoinFlip
+pub struct CoinFlipAccounts<'a, 'b: 'a> {
+    pub player: &'a AccountInfo<'b>,
+    pub coinflip_pda: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub player_associated_token: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub holding: Option<HoldingProof>,
+}
+impl<'a, 'b: 'a> CoinFlipAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let player =  next_account_info(account_info_iter)?; // sender or signer
+        let coinflip_pda = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
+        let player_associated_token = next_account_info(account_info_iter)?; // spl token mint associate account
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let system_program = next_account_info(account_info_iter)?;
+        let pda =next_account_info(account_info_iter)?;  // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let holding_proof = next_account_info(account_info_iter).ok(); // holder record of the player or its stake entry, only required with a holding period
+
+        assert_signer(player)?;
+        assert_writable(player)?;
+        //the coinflip account is created by the player so it has to sign as well
+        assert_signer(coinflip_pda)?;
+        assert_writable(coinflip_pda)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            msg!("NFT owner doesn't match");
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_associated_token(player_associated_token, player.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(player_associated_token)?;
+        assert_system_program(system_program)?;
+        let mut holding = None;
+        if escrow.min_holding_period > 0
+        {
+            let holding_proof = holding_proof.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            holding = Some(if holding_proof.owner == program_id {
+                let stake_entry = StakeEntry::load(holding_proof, program_id)?;
+                let (stake_pool, _) = generate_pda_and_bump_seed(STAKEPREFIX, nft_owner.key, pda.key, program_id);
+                if stake_entry.owner != *player.key || stake_entry.pool != stake_pool
+                {
+                    return Err(TokenError::InvalidStakeEntry.into());
+                }
+                HoldingProof::Stake(stake_entry)
+            } else {
+                HoldingProof::Holder(Box::new(load_holder_record(holding_proof, spl_token_mint, player.key)?))
+            });
+        }
+
+        Ok(Self{
+            player,
+            coinflip_pda,
+            token_program_id,
+            spl_vault_associated_address,
+            player_associated_token,
+            spl_token_mint,
+            nft_owner,
+            system_program,
+            pda,
+            nft_vault,
+            escrow,
+            holding,
+        })
+    }
+}
+
+/// Accounts of ProcessClaimCoinFlip
+pub struct ClaimCoinFlipAccounts<'a, 'b: 'a> {
+    pub player: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub coinflip_pda: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub buyer_spl_associated: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub treasury: &'a AccountInfo<'b>,
+    pub proceeds_info: &'a AccountInfo<'b>,
+    pub player_quote: &'a AccountInfo<'b>,
+    pub owner_quote: &'a AccountInfo<'b>,
+    pub proceeds_quote: &'a AccountInfo<'b>,
+    pub metadata: &'a AccountInfo<'b>,
+    /// Trailing accounts of the verified creators of the nft
+    pub creators: &'a [AccountInfo<'b>],
+    pub escrow: NftDetails,
+    pub coinflip: CoinFlip,
+    pub config: Config,
+    /// Beneficiaries of the vault, None if the nft owner is paid directly
+    pub proceeds: Option<Proceeds>,
+    pub royalties: Royalties,
+    pub quote: Quote<'a, 'b>,
+    pub nft_vault_bump: u8,
+    pub spl_token_bump: u8,
+}
+impl<'a, 'b: 'a> ClaimCoinFlipAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let player =  next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
+        let coinflip_pda = next_account_info(account_info_iter)?; // pda data for coinflip
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
+        let buyer_spl_associated =  next_account_info(account_info_iter)?; // sender or signer
+        let spl_token_mint=next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info=next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?; // protocol config pda from CONFIGPREFIX
+        let treasury = next_account_info(account_info_iter)?; // protocol treasury set in the config
+        let proceeds_info = next_account_info(account_info_iter)?; // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let player_quote = next_account_info(account_info_iter)?; // quote account of the player, the player itself for SOL
+        let owner_quote = next_account_info(account_info_iter)?; // quote account of the nft owner
+        let proceeds_quote = next_account_info(account_info_iter)?; // quote account of the proceeds pda
+        let metadata = next_account_info(account_info_iter)?; // metaplex metadata of the nft
+        let creators = account_info_iter.as_slice(); // verified creators of the nft metadata
+
+        assert_signer(player)?;
+        assert_writable(player)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            msg!("NFT owner doesn't match");
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(nft_owner)?;
+        assert_writable(coinflip_pda)?;
+        let coinflip = CoinFlip::load(coinflip_pda, program_id)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        let spl_token_bump = assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_writable(spl_token_mint)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_associated_token(buyer_spl_associated, player.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(buyer_spl_associated)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        let config = validate_fee_accounts(program_id, config_info, treasury, &quote)?;
+        let proceeds = validate_proceeds_account(program_id, proceeds_info, nft_owner, pda, &escrow)?;
+        quote.assert_account(player_quote, player.key)?;
+        quote.assert_account(owner_quote, nft_owner.key)?;
+        if proceeds.is_some()
+        {
+            quote.assert_account(proceeds_quote, proceeds_info.key)?;
+        }
+        let royalties = Royalties::load(metadata, &escrow.nft_mint)?;
+
+        Ok(Self{
+            player,
+            nft_owner,
+            pda,
+            coinflip_pda,
+            token_program_id,
+            nft_vault,
+            spl_vault_associated_address,
+            buyer_spl_associated,
+            spl_token_mint,
+            system_program,
+            rent_info,
+            config_info,
+            treasury,
+            proceeds_info,
+            player_quote,
+            owner_quote,
+            proceeds_quote,
+            metadata,
+            creators,
+            escrow,
+            coinflip,
+            config,
+            proceeds,
+            royalties,
+            quote,
+            nft_vault_bump,
+            spl_token_bump,
+        })
+    }
+}
+
+/// Accounts of MigrateVault
+pub struct MigrateVaultAccounts<'a, 'b: 'a> {
+    pub payer: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+impl<'a, 'b: 'a> MigrateVaultAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let payer =  next_account_info(account_info_iter)?; // sender or signer, tops up rent
+        let pda = next_account_info(account_info_iter)?; // pda data in the legacy layout
+        let system_program = next_account_info(account_info_iter)?; //system program
+
+        assert_signer(payer)?;
+        assert_writable(payer)?;
+        assert_owned_by(pda, program_id)?;
+        assert_writable(pda)?;
+        assert_system_program(system_program)?;
+
+        Ok(Self{
+            payer,
+            pda,
+            system_program,
+        })
+    }
+}
+
+/// Accounts of InitConfig
+pub struct InitConfigAccounts<'a, 'b: 'a> {
+    pub authority: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub program_data: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub config_bump: u8,
+}
+impl<'a, 'b: 'a> InitConfigAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?; // program upgrade authority, pays for the config
+        let config_info = next_account_info(account_info_iter)?; // config pda from CONFIGPREFIX
+        let program_data = next_account_info(account_info_iter)?; // program data account of this program
+        let system_program = next_account_info(account_info_iter)?; //system program
+
+        assert_signer(authority)?;
+        assert_writable(authority)?;
+        let (config_address, config_bump) = Pubkey::find_program_address(&[CONFIGPREFIX.as_bytes()], program_id);
+        if config_address != *config_info.key
+        {
+            return Err(TokenError::InvalidConfig.into());
+        }
+        assert_writable(config_info)?;
+        //only the upgrade authority may create the config so it can't be front run
+        if get_upgrade_authority(program_data, program_id)? != Some(*authority.key)
+        {
+            msg!("Only the program upgrade authority can create the config");
+            return Err(TokenError::InvalidAuthority.into());
+        }
+        assert_system_program(system_program)?;
+
+        Ok(Self{
+            authority,
+            config_info,
+            program_data,
+            system_program,
+            config_bump,
+        })
+    }
+}
+
+/// Accounts of UpdateConfig
+pub struct UpdateConfigAccounts<'a, 'b: 'a> {
+    pub authority: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub config: Config,
+}
+impl<'a, 'b: 'a> UpdateConfigAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?; // config authority
+        let config_info = next_account_info(account_info_iter)?; // config pda from CONFIGPREFIX
+
+        assert_signer(authority)?;
+        let (config_address, _config_bump) = Pubkey::find_program_address(&[CONFIGPREFIX.as_bytes()], program_id);
+        if config_address != *config_info.key
+        {
+            return Err(TokenError::InvalidConfig.into());
+        }
+        assert_writable(config_info)?;
+        let config = Config::load(config_info, program_id)?;
+        if config.authority != *authority.key
+        {
+            return Err(TokenError::InvalidAuthority.into());
+        }
+
+        Ok(Self{
+            authority,
+            config_info,
+            config,
+        })
+    }
+}
+
+/// Accounts of WithdrawProceeds
+pub struct WithdrawProceedsAccounts<'a, 'b: 'a> {
+    pub beneficiary: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub proceeds_info: &'a AccountInfo<'b>,
+    pub proceeds_quote: &'a AccountInfo<'b>,
+    pub beneficiary_quote: &'a AccountInfo<'b>,
+    pub proceeds: Proceeds,
+    pub quote: Quote<'a, 'b>,
+    pub proceeds_bump: u8,
+}
+impl<'a, 'b: 'a> WithdrawProceedsAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let beneficiary = next_account_info(account_info_iter)?; // sender or signer, receives the balance
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let proceeds_info = next_account_info(account_info_iter)?; // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?;
+        let proceeds_quote = next_account_info(account_info_iter)?; // quote account of the proceeds pda, the pda itself for SOL
+        let beneficiary_quote = next_account_info(account_info_iter)?; // quote account of the beneficiary
+
+        assert_signer(beneficiary)?;
+        assert_writable(beneficiary)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let proceeds_bump = assert_pda(proceeds_info, PROCEEDSPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidProceeds)?;
+        let proceeds = validate_proceeds_account(program_id, proceeds_info, nft_owner, pda, &escrow)?
+            .ok_or(TokenError::InvalidProceeds)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(proceeds_quote, proceeds_info.key)?;
+        quote.assert_account(beneficiary_quote, beneficiary.key)?;
+
+        Ok(Self{
+            beneficiary,
+            nft_owner,
+            pda,
+            proceeds_info,
+            proceeds_quote,
+            beneficiary_quote,
+            proceeds,
+            quote,
+            proceeds_bump,
+        })
+    }
+}
+
+/// Accounts of InitStakePool
+pub struct InitStakePoolAccounts<'a, 'b: 'a> {
+    pub payer: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub stake_pool_info: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub pool_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    /// Token program of the quote mint
+    pub quote_token_program: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    /// Quote mint of the vault, only required for an SPL quote mint
+    pub quote_mint_info: Option<&'a AccountInfo<'b>>,
+    /// Associated address from stake pool and quote mint, receives the auction proceeds
+    pub pool_quote: Option<&'a AccountInfo<'b>>,
+    pub stake_pool_bump: u8,
+}
+impl<'a, 'b: 'a> InitStakePoolAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?; // sender or signer, pays for the pool
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let stake_pool_info = next_account_info(account_info_iter)?; // stake pool pda from STAKEPREFIX, nft_owner and pda
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let pool_token_account = next_account_info(account_info_iter)?; // associated address from stake pool and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let quote_mint_info = next_account_info(account_info_iter).ok(); // quote mint
+        let pool_quote = next_account_info(account_info_iter).ok(); // associated address from stake pool and quote mint
+
+        assert_signer(payer)?;
+        assert_writable(payer)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let stake_pool_bump = assert_pda(stake_pool_info, STAKEPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidStakePool)?;
+        assert_writable(stake_pool_info)?;
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(pool_token_account, stake_pool_info.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(pool_token_account)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+        if escrow.quote_mint != Pubkey::default()
+        {
+            let quote_mint_info = quote_mint_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *quote_mint_info.key != escrow.quote_mint
+            {
+                return Err(TokenError::InvalidMint.into());
+            }
+            let pool_quote = pool_quote.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            assert_associated_token(pool_quote, stake_pool_info.key, &escrow.quote_mint, &escrow.quote_token_program)?;
+            assert_writable(pool_quote)?;
+        }
+
+        Ok(Self{
+            payer,
+            nft_owner,
+            pda,
+            stake_pool_info,
+            spl_token_mint,
+            pool_token_account,
+            token_program_id,
+            quote_token_program,
+            associated_token_info,
+            system_program,
+            rent_info,
+            quote_mint_info,
+            pool_quote,
+            stake_pool_bump,
+        })
+    }
+}
+
+/// Accounts of Stake, Unstake and ClaimStakeRewards
+pub struct StakeAccounts<'a, 'b: 'a> {
+    pub staker: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub stake_pool_info: &'a AccountInfo<'b>,
+    pub stake_entry_info: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub staker_token_account: &'a AccountInfo<'b>,
+    pub pool_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// Quote account of the stake pool, the pool itself for SOL
+    pub pool_quote: &'a AccountInfo<'b>,
+    /// Quote account of the staker, the staker itself for SOL
+    pub staker_quote: &'a AccountInfo<'b>,
+    pub stake_pool: StakePool,
+    /// Stake entry of the staker, None if the account is still empty
+    pub stake_entry: Option<StakeEntry>,
+    pub stake_pool_bump: u8,
+    pub stake_entry_bump: u8,
+    pub quote: Quote<'a, 'b>,
+}
+impl<'a, 'b: 'a> StakeAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let staker = next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let stake_pool_info = next_account_info(account_info_iter)?; // stake pool pda from STAKEPREFIX, nft_owner and pda
+        let stake_entry_info = next_account_info(account_info_iter)?; // stake entry pda from STAKEENTRYPREFIX, staker and stake pool
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let staker_token_account = next_account_info(account_info_iter)?; // associated address from staker and spl token mint
+        let pool_token_account = next_account_info(account_info_iter)?; // associated address from stake pool and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?;
+        let pool_quote = next_account_info(account_info_iter).unwrap_or(stake_pool_info); // quote account of the stake pool, only required for an SPL quote mint
+        let staker_quote = next_account_info(account_info_iter).unwrap_or(staker); // quote account of the staker, only required for an SPL quote mint
+
+        assert_signer(staker)?;
+        assert_writable(staker)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let stake_pool_bump = assert_pda(stake_pool_info, STAKEPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidStakePool)?;
+        assert_writable(stake_pool_info)?;
+        let stake_pool = StakePool::load(stake_pool_info, program_id)?;
+        if stake_pool.vault != *pda.key
+        {
+            return Err(TokenError::InvalidStakePool.into());
+        }
+        let stake_entry_bump = assert_pda(stake_entry_info, STAKEENTRYPREFIX, staker.key, stake_pool_info.key, program_id, TokenError::InvalidStakeEntry)?;
+        assert_writable(stake_entry_info)?;
+        let stake_entry = if stake_entry_info.data_is_empty() {
+            None
+        } else {
+            Some(StakeEntry::load(stake_entry_info, program_id)?)
+        };
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(staker_token_account, staker.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(staker_token_account)?;
+        assert_associated_token(pool_token_account, stake_pool_info.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(pool_token_account)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(pool_quote, stake_pool_info.key)?;
+        quote.assert_account(staker_quote, staker.key)?;
+
+        Ok(Self{
+            staker,
+            nft_owner,
+            pda,
+            stake_pool_info,
+            stake_entry_info,
+            spl_token_mint,
+            staker_token_account,
+            pool_token_account,
+            token_program_id,
+            system_program,
+            pool_quote,
+            staker_quote,
+            stake_pool,
+            stake_entry,
+            stake_pool_bump,
+            stake_entry_bump,
+            quote,
+        })
+    }
+}
+
+/// Accounts of CreateProposal
+pub struct CreateProposalAccounts<'a, 'b: 'a> {
+    pub proposer: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub proposal_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+}
+impl<'a, 'b: 'a> CreateProposalAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let proposer = next_account_info(account_info_iter)?; // sender or signer, pays for the proposal
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let proposal_info = next_account_info(account_info_iter)?; // new proposal account
+        let system_program = next_account_info(account_info_iter)?;
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let spl_vault_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and spl token mint
+
+        assert_signer(proposer)?;
+        assert_writable(proposer)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        //the proposal account is created by the proposer so it has to sign as well
+        assert_signer(proposal_info)?;
+        assert_writable(proposal_info)?;
+        assert_system_program(system_program)?;
+        assert_pda(nft_vault, NFTPREFIX, &escrow.nft_owner, pda.key, program_id, TokenError::InvalidVault)?;
+        if escrow.token_mint != *spl_token_mint.key
+        {
+            return Err(TokenError::InvalidMint.into());
+        }
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+
+        Ok(Self{
+            proposer,
+            pda,
+            proposal_info,
+            system_program,
+            spl_token_mint,
+            spl_vault_associated_address,
+            escrow,
+        })
+    }
+}
+
+/// Accounts of CastVote
+pub struct CastVoteAccounts<'a, 'b: 'a> {
+    pub voter: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub proposal_info: &'a AccountInfo<'b>,
+    pub vote_info: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub voter_token_account: &'a AccountInfo<'b>,
+    pub vote_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub proposal: Proposal,
+    pub vote_bump: u8,
+}
+impl<'a, 'b: 'a> CastVoteAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let voter = next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let proposal_info = next_account_info(account_info_iter)?; // proposal voted on
+        let vote_info = next_account_info(account_info_iter)?; // vote pda from VOTEPREFIX, voter and proposal
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let voter_token_account = next_account_info(account_info_iter)?; // associated address from voter and spl token mint
+        let vote_token_account = next_account_info(account_info_iter)?; // associated address from vote and spl token mint, escrows the votes
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+
+        assert_signer(voter)?;
+        assert_writable(voter)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(proposal_info)?;
+        let proposal = Proposal::load(proposal_info, program_id)?;
+        if proposal.vault != *pda.key
+        {
+            return Err(TokenError::InvalidProposal.into());
+        }
+        let vote_bump = assert_pda(vote_info, VOTEPREFIX, voter.key, proposal_info.key, program_id, TokenError::InvalidVote)?;
+        assert_writable(vote_info)?;
+        //a holder votes once, the escrowed tokens can't be moved to vote again
+        if !vote_info.data_is_empty()
+        {
+            msg!("Already voted");
+            return Err(TokenError::InvalidVote.into());
+        }
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(voter_token_account, voter.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(voter_token_account)?;
+        assert_associated_token(vote_token_account, vote_info.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(vote_token_account)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+
+        Ok(Self{
+            voter,
+            nft_owner,
+            pda,
+            proposal_info,
+            vote_info,
+            spl_token_mint,
+            voter_token_account,
+            vote_token_account,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            proposal,
+            vote_bump,
+        })
+    }
+}
+
+/// Accounts of ExecuteProposal
+pub struct ExecuteProposalAccounts<'a, 'b: 'a> {
+    pub pda: &'a AccountInfo<'b>,
+    pub proposal_info: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub proposal: Proposal,
+}
+impl<'a, 'b: 'a> ExecuteProposalAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let proposal_info = next_account_info(account_info_iter)?; // passed proposal
+
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        assert_writable(proposal_info)?;
+        let proposal = Proposal::load(proposal_info, program_id)?;
+        if proposal.vault != *pda.key
+        {
+            return Err(TokenError::InvalidProposal.into());
+        }
+
+        Ok(Self{
+            pda,
+            proposal_info,
+            escrow,
+            proposal,
+        })
+    }
+}
+
+/// Accounts of WithdrawVote
+pub struct WithdrawVoteAccounts<'a, 'b: 'a> {
+    pub voter: &'a AccountInfo<'b>,
+    pub proposal_info: &'a AccountInfo<'b>,
+    pub vote_info: &'a AccountInfo<'b>,
+    pub voter_token_account: &'a AccountInfo<'b>,
+    pub vote_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub vote: Vote,
+    /// Voting on the proposal or buyout is over so the escrowed fractions can be released
+    pub voting_closed: bool,
+    pub vote_bump: u8,
+}
+impl<'a, 'b: 'a> WithdrawVoteAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], now: u64) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let voter = next_account_info(account_info_iter)?; // sender or signer
+        let proposal_info = next_account_info(account_info_iter)?; // proposal or buyout voted on
+        let vote_info = next_account_info(account_info_iter)?; // vote pda from VOTEPREFIX, voter and proposal
+        let voter_token_account = next_account_info(account_info_iter)?; // associated address from voter and spl token mint
+        let vote_token_account = next_account_info(account_info_iter)?; // associated address from vote and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+
+        assert_signer(voter)?;
+        let voting_closed = match Proposal::load(proposal_info, program_id) {
+            Ok(proposal) => !proposal.voting_open(now),
+            Err(_) => {
+                let buyout = Buyout::load(proposal_info, program_id)?;
+                buyout.status != BuyoutStatus::Open || now >= buyout.deadline
+            }
+        };
+        let vote_bump = assert_pda(vote_info, VOTEPREFIX, voter.key, proposal_info.key, program_id, TokenError::InvalidVote)?;
+        assert_writable(vote_info)?;
+        let vote = Vote::load(vote_info, program_id)?;
+        assert_writable(voter_token_account)?;
+        assert_writable(vote_token_account)?;
+        assert_supported_token_program(token_program_id)?;
+        //the escrow account was created under the token program of the vault
+        assert_owned_by(vote_token_account, token_program_id.key)?;
+
+        Ok(Self{
+            voter,
+            proposal_info,
+            vote_info,
+            voter_token_account,
+            vote_token_account,
+            token_program_id,
+            spl_token_mint,
+            vote,
+            voting_closed,
+            vote_bump,
+        })
+    }
+}
+
+/// Accounts of CreateBuyout
+pub struct CreateBuyoutAccounts<'a, 'b: 'a> {
+    pub bidder: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub buyout_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    /// Token program of the quote mint
+    pub quote_token_program: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    /// Quote mint of the vault, only required for an SPL quote mint
+    pub quote_mint_info: Option<&'a AccountInfo<'b>>,
+    /// Quote account of the bidder, the bidder itself for SOL
+    pub bidder_quote: &'a AccountInfo<'b>,
+    /// Quote account of the buyout, escrows the offer, the buyout itself for SOL
+    pub buyout_quote: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub quote: Quote<'a, 'b>,
+    pub buyout_bump: u8,
+}
+impl<'a, 'b: 'a> CreateBuyoutAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let bidder = next_account_info(account_info_iter)?; // sender or signer, escrows the offer
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let buyout_info = next_account_info(account_info_iter)?; // buyout pda from BUYOUTPREFIX, bidder and pda
+        let system_program = next_account_info(account_info_iter)?;
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let spl_vault_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and spl token mint
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let quote_mint_info = next_account_info(account_info_iter).ok(); // quote mint, only required for an SPL quote mint
+        let bidder_quote = next_account_info(account_info_iter).unwrap_or(bidder); // quote account of the bidder, only required for an SPL quote mint
+        let buyout_quote = next_account_info(account_info_iter).unwrap_or(buyout_info); // associated address from buyout and quote mint, only required for an SPL quote mint
+
+        assert_signer(bidder)?;
+        assert_writable(bidder)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        let buyout_bump = assert_pda(buyout_info, BUYOUTPREFIX, bidder.key, pda.key, program_id, TokenError::InvalidBuyout)?;
+        assert_writable(buyout_info)?;
+        assert_system_program(system_program)?;
+        assert_pda(nft_vault, NFTPREFIX, &escrow.nft_owner, pda.key, program_id, TokenError::InvalidVault)?;
+        if escrow.token_mint != *spl_token_mint.key
+        {
+            return Err(TokenError::InvalidMint.into());
+        }
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_rent_sysvar(rent_info)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        if !quote.is_native()
+        {
+            let quote_mint_info = quote_mint_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *quote_mint_info.key != escrow.quote_mint
+            {
+                return Err(TokenError::InvalidMint.into());
+            }
+        }
+        quote.assert_account(bidder_quote, bidder.key)?;
+        quote.assert_account(buyout_quote, buyout_info.key)?;
+
+        Ok(Self{
+            bidder,
+            pda,
+            buyout_info,
+            system_program,
+            spl_token_mint,
+            spl_vault_associated_address,
+            quote_token_program,
+            associated_token_info,
+            rent_info,
+            quote_mint_info,
+            bidder_quote,
+            buyout_quote,
+            escrow,
+            quote,
+            buyout_bump,
+        })
+    }
+}
+
+/// Accounts of CastBuyoutVote
+pub struct BuyoutVoteAccounts<'a, 'b: 'a> {
+    pub voter: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub buyout_info: &'a AccountInfo<'b>,
+    pub vote_info: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub voter_token_account: &'a AccountInfo<'b>,
+    pub vote_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub buyout: Buyout,
+    pub vote_bump: u8,
+}
+impl<'a, 'b: 'a> BuyoutVoteAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let voter = next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let buyout_info = next_account_info(account_info_iter)?; // buyout voted on
+        let vote_info = next_account_info(account_info_iter)?; // vote pda from VOTEPREFIX, voter and buyout
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let voter_token_account = next_account_info(account_info_iter)?; // associated address from voter and spl token mint
+        let vote_token_account = next_account_info(account_info_iter)?; // associated address from vote and spl token mint, escrows the votes
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+
+        assert_signer(voter)?;
+        assert_writable(voter)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(buyout_info)?;
+        let buyout = Buyout::load(buyout_info, program_id)?;
+        if buyout.vault != *pda.key
+        {
+            return Err(TokenError::InvalidBuyout.into());
+        }
+        let vote_bump = assert_pda(vote_info, VOTEPREFIX, voter.key, buyout_info.key, program_id, TokenError::InvalidVote)?;
+        assert_writable(vote_info)?;
+        if !vote_info.data_is_empty()
+        {
+            msg!("Already voted");
+            return Err(TokenError::InvalidVote.into());
+        }
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(voter_token_account, voter.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(voter_token_account)?;
+        assert_associated_token(vote_token_account, vote_info.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(vote_token_account)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+
+        Ok(Self{
+            voter,
+            nft_owner,
+            pda,
+            buyout_info,
+            vote_info,
+            spl_token_mint,
+            voter_token_account,
+            vote_token_account,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            buyout,
+            vote_bump,
+        })
+    }
+}
+
+/// Accounts of ExecuteBuyout
+pub struct ExecuteBuyoutAccounts<'a, 'b: 'a> {
+    pub payer: &'a AccountInfo<'b>,
+    pub bidder: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub buyout_info: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub nft_associated_address: &'a AccountInfo<'b>,
+    pub bidder_nft_account: &'a AccountInfo<'b>,
+    pub nft_mint: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub buyout: Buyout,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> ExecuteBuyoutAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?; // sender or signer, pays for the bidder token account
+        let bidder = next_account_info(account_info_iter)?; // bidder of the buyout
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let buyout_info = next_account_info(account_info_iter)?; // accepted buyout
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let nft_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and nft mint
+        let bidder_nft_account = next_account_info(account_info_iter)?; // associated address from bidder and nft mint
+        let nft_mint = next_account_info(account_info_iter)?; // mint address of nft
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let spl_vault_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the nft mint
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+
+        assert_signer(payer)?;
+        assert_writable(payer)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(buyout_info)?;
+        let buyout = Buyout::load(buyout_info, program_id)?;
+        if buyout.vault != *pda.key || buyout.bidder != *bidder.key
+        {
+            return Err(TokenError::InvalidBuyout.into());
+        }
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        if escrow.nft_mint != *nft_mint.key
+        {
+            return Err(TokenError::InvalidMint.into());
+        }
+        assert_associated_token(nft_associated_address, nft_vault.key, nft_mint.key, &escrow.nft_token_program)?;
+        assert_writable(nft_associated_address)?;
+        assert_associated_token(bidder_nft_account, bidder.key, nft_mint.key, &escrow.nft_token_program)?;
+        assert_writable(bidder_nft_account)?;
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        //the nft changes hands here, so this is the token program of the nft mint
+        assert_token_program(token_program_id, &escrow.nft_token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+
+        Ok(Self{
+            payer,
+            bidder,
+            nft_owner,
+            pda,
+            buyout_info,
+            nft_vault,
+            nft_associated_address,
+            bidder_nft_account,
+            nft_mint,
+            spl_token_mint,
+            spl_vault_associated_address,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            escrow,
+            buyout,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Accounts of RefundBuyout
+pub struct RefundBuyoutAccounts<'a, 'b: 'a> {
+    pub bidder: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub buyout_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// Quote account of the buyout, the buyout itself for SOL
+    pub buyout_quote: &'a AccountInfo<'b>,
+    /// Quote account of the bidder, the bidder itself for SOL
+    pub bidder_quote: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub buyout: Buyout,
+    pub quote: Quote<'a, 'b>,
+    pub buyout_bump: u8,
+}
+impl<'a, 'b: 'a> RefundBuyoutAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let bidder = next_account_info(account_info_iter)?; // bidder of the buyout, receives the refund
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let buyout_info = next_account_info(account_info_iter)?; // rejected buyout
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?;
+        let buyout_quote = next_account_info(account_info_iter).unwrap_or(buyout_info); // quote account of the buyout, only required for an SPL quote mint
+        let bidder_quote = next_account_info(account_info_iter).unwrap_or(bidder); // quote account of the bidder, only required for an SPL quote mint
+
+        assert_writable(bidder)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        assert_writable(buyout_info)?;
+        let buyout = Buyout::load(buyout_info, program_id)?;
+        if buyout.vault != *pda.key || buyout.bidder != *bidder.key
+        {
+            return Err(TokenError::InvalidBuyout.into());
+        }
+        let buyout_bump = assert_pda(buyout_info, BUYOUTPREFIX, bidder.key, pda.key, program_id, TokenError::InvalidBuyout)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(buyout_quote, buyout_info.key)?;
+        quote.assert_account(bidder_quote, bidder.key)?;
+
+        Ok(Self{
+            bidder,
+            pda,
+            buyout_info,
+            system_program,
+            buyout_quote,
+            bidder_quote,
+            escrow,
+            buyout,
+            quote,
+            buyout_bump,
+        })
+    }
+}
+
+/// Accounts of RedeemBuyout
+pub struct RedeemBuyoutAccounts<'a, 'b: 'a> {
+    pub holder: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub buyout_info: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub holder_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// Quote account of the buyout, the buyout itself for SOL
+    pub buyout_quote: &'a AccountInfo<'b>,
+    /// Quote account of the holder, the holder itself for SOL
+    pub holder_quote: &'a AccountInfo<'b>,
+    pub buyout: Buyout,
+    pub quote: Quote<'a, 'b>,
+    pub buyout_bump: u8,
+}
+impl<'a, 'b: 'a> RedeemBuyoutAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let holder = next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let buyout_info = next_account_info(account_info_iter)?; // accepted buyout
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let holder_token_account = next_account_info(account_info_iter)?; // associated address from holder and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?;
+        let buyout_quote = next_account_info(account_info_iter).unwrap_or(buyout_info); // quote account of the buyout, only required for an SPL quote mint
+        let holder_quote = next_account_info(account_info_iter).unwrap_or(holder); // quote account of the holder, only required for an SPL quote mint
+
+        assert_signer(holder)?;
+        assert_writable(holder)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(buyout_info)?;
+        let buyout = Buyout::load(buyout_info, program_id)?;
+        if buyout.vault != *pda.key
+        {
+            return Err(TokenError::InvalidBuyout.into());
+        }
+        let buyout_bump = assert_pda(buyout_info, BUYOUTPREFIX, &buyout.bidder, pda.key, program_id, TokenError::InvalidBuyout)?;
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_writable(spl_token_mint)?;
+        assert_associated_token(holder_token_account, holder.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(holder_token_account)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(buyout_quote, buyout_info.key)?;
+        quote.assert_account(holder_quote, holder.key)?;
+
+        Ok(Self{
+            holder,
+            nft_owner,
+            pda,
+            buyout_info,
+            spl_token_mint,
+            holder_token_account,
+            token_program_id,
+            system_program,
+            buyout_quote,
+            holder_quote,
+            buyout,
+            quote,
+            buyout_bump,
+        })
+    }
+}
+
+/// Accounts of ClaimSaleRefund, anyone can settle the premium of a buyer once the dutch sale ended
+pub struct ClaimSaleRefundAccounts<'a, 'b: 'a> {
+    pub buyer: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub purchase_info: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub treasury: &'a AccountInfo<'b>,
+    pub proceeds_info: &'a AccountInfo<'b>,
+    pub vault_quote: &'a AccountInfo<'b>,
+    pub buyer_quote: &'a AccountInfo<'b>,
+    pub owner_quote: &'a AccountInfo<'b>,
+    pub proceeds_quote: &'a AccountInfo<'b>,
+    pub metadata: &'a AccountInfo<'b>,
+    /// Trailing accounts of the verified creators of the nft
+    pub creators: &'a [AccountInfo<'b>],
+    pub escrow: NftDetails,
+    pub config: Config,
+    /// Beneficiaries of the vault, None if the nft owner is paid directly
+    pub proceeds: Option<Proceeds>,
+    pub royalties: Royalties,
+    pub quote: Quote<'a, 'b>,
+    pub purchase: Purchase,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> ClaimSaleRefundAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let buyer = next_account_info(account_info_iter)?; // buyer of the purchase
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let purchase_info = next_account_info(account_info_iter)?; // purchase pda from PURCHASEPREFIX, buyer and pda
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?; //system program
+        let config_info = next_account_info(account_info_iter)?; // protocol config pda from CONFIGPREFIX
+        let treasury = next_account_info(account_info_iter)?; // protocol treasury set in the config
+        let proceeds_info = next_account_info(account_info_iter)?; // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault
+        let buyer_quote = next_account_info(account_info_iter)?; // quote account of the buyer
+        let owner_quote = next_account_info(account_info_iter)?; // quote account of the nft owner
+        let proceeds_quote = next_account_info(account_info_iter)?; // quote account of the proceeds pda
+        let metadata = next_account_info(account_info_iter)?; // metaplex metadata of the nft
+        let creators = account_info_iter.as_slice(); // verified creators of the nft metadata
+
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(nft_owner)?;
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_writable(nft_vault)?;
+        assert_pda(purchase_info, PURCHASEPREFIX, buyer.key, pda.key, program_id, TokenError::InvalidPurchase)?;
+        assert_writable(purchase_info)?;
+        let purchase = Purchase::load(purchase_info, program_id)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        let config = validate_fee_accounts(program_id, config_info, treasury, &quote)?;
+        let proceeds = validate_proceeds_account(program_id, proceeds_info, nft_owner, pda, &escrow)?;
+        quote.assert_account(vault_quote, nft_vault.key)?;
+        quote.assert_account(buyer_quote, buyer.key)?;
+        quote.assert_account(owner_quote, nft_owner.key)?;
+        if proceeds.is_some()
+        {
+            quote.assert_account(proceeds_quote, proceeds_info.key)?;
+        }
+        let royalties = Royalties::load(metadata, &escrow.nft_mint)?;
+
+        Ok(Self{
+            buyer,
+            nft_owner,
+            pda,
+            nft_vault,
+            purchase_info,
+            token_program_id,
+            system_program,
+            config_info,
+            treasury,
+            proceeds_info,
+            vault_quote,
+            buyer_quote,
+            owner_quote,
+            proceeds_quote,
+            metadata,
+            creators,
+            escrow,
+            config,
+            proceeds,
+            royalties,
+            quote,
+            purchase,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Accounts of SellToCurve
+pub struct SellToCurveAccounts<'a, 'b: 'a> {
+    pub seller: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub seller_token_account: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub vault_quote: &'a AccountInfo<'b>,
+    pub seller_quote: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub quote: Quote<'a, 'b>,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> SellToCurveAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let seller = next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let seller_token_account = next_account_info(account_info_iter)?; // associated address from seller and spl token mint
+        let spl_vault_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?; //system program
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault
+        let seller_quote = next_account_info(account_info_iter)?; // quote account of the seller
+
+        assert_signer(seller)?;
+        assert_writable(seller)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_writable(nft_vault)?;
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_associated_token(seller_token_account, seller.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(seller_token_account)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(vault_quote, nft_vault.key)?;
+        quote.assert_account(seller_quote, seller.key)?;
+
+        Ok(Self{
+            seller,
+            nft_owner,
+            pda,
+            nft_vault,
+            spl_token_mint,
+            seller_token_account,
+            spl_vault_associated_address,
+            token_program_id,
+            system_program,
+            vault_quote,
+            seller_quote,
+            escrow,
+            quote,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Accounts of QuoteSale
+pub struct QuoteSaleAccounts<'a, 'b: 'a> {
+    pub pda: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+}
+impl<'a, 'b: 'a> QuoteSaleAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let pda = next_account_info(account_info_iter)?; // main data account
+
+        let escrow = NftDetails::load(pda, program_id)?;
+
+        Ok(Self{
+            pda,
+            escrow,
+        })
+    }
+}
+
+/// Accounts of WithdrawSaleReserve, anyone can pay out the reserve once the sale ended
+pub struct WithdrawSaleReserveAccounts<'a, 'b: 'a> {
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub treasury: &'a AccountInfo<'b>,
+    pub proceeds_info: &'a AccountInfo<'b>,
+    pub vault_quote: &'a AccountInfo<'b>,
+    pub owner_quote: &'a AccountInfo<'b>,
+    pub proceeds_quote: &'a AccountInfo<'b>,
+    pub metadata: &'a AccountInfo<'b>,
+    /// Trailing accounts of the verified creators of the nft
+    pub creators: &'a [AccountInfo<'b>],
+    pub escrow: NftDetails,
+    pub config: Config,
+    /// Beneficiaries of the vault, None if the nft owner is paid directly
+    pub proceeds: Option<Proceeds>,
+    pub royalties: Royalties,
+    pub quote: Quote<'a, 'b>,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> WithdrawSaleReserveAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?; //system program
+        let config_info = next_account_info(account_info_iter)?; // protocol config pda from CONFIGPREFIX
+        let treasury = next_account_info(account_info_iter)?; // protocol treasury set in the config
+        let proceeds_info = next_account_info(account_info_iter)?; // proceeds pda from PROCEEDSPREFIX, nft_owner and pda
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault
+        let owner_quote = next_account_info(account_info_iter)?; // quote account of the nft owner
+        let proceeds_quote = next_account_info(account_info_iter)?; // quote account of the proceeds pda
+        let metadata = next_account_info(account_info_iter)?; // metaplex metadata of the nft
+        let creators = account_info_iter.as_slice(); // verified creators of the nft metadata
+
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        assert_writable(nft_owner)?;
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_writable(nft_vault)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        let config = validate_fee_accounts(program_id, config_info, treasury, &quote)?;
+        let proceeds = validate_proceeds_account(program_id, proceeds_info, nft_owner, pda, &escrow)?;
+        quote.assert_account(vault_quote, nft_vault.key)?;
+        quote.assert_account(owner_quote, nft_owner.key)?;
+        if proceeds.is_some()
+        {
+            quote.assert_account(proceeds_quote, proceeds_info.key)?;
+        }
+        let royalties = Royalties::load(metadata, &escrow.nft_mint)?;
+
+        Ok(Self{
+            nft_owner,
+            pda,
+            nft_vault,
+            token_program_id,
+            system_program,
+            config_info,
+            treasury,
+            proceeds_info,
+            vault_quote,
+            owner_quote,
+            proceeds_quote,
+            metadata,
+            creators,
+            escrow,
+            config,
+            proceeds,
+            royalties,
+            quote,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Accounts of RefundCrowdfund
+pub struct RefundCrowdfundAccounts<'a, 'b: 'a> {
+    pub holder: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub holder_token_account: &'a AccountInfo<'b>,
+    pub purchase_info: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub vault_quote: &'a AccountInfo<'b>,
+    pub holder_quote: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    /// Purchase of the holder, records what the holder paid in the sale
+    pub purchase: Purchase,
+    pub quote: Quote<'a, 'b>,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> RefundCrowdfundAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let holder = next_account_info(account_info_iter)?; // sender or signer
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let holder_token_account = next_account_info(account_info_iter)?; // associated address from holder and spl token mint
+        let purchase_info = next_account_info(account_info_iter)?; // purchase pda from PURCHASEPREFIX, holder and pda
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let quote_token_program = next_account_info(account_info_iter)?; // token program of the quote mint, the fraction token program for SOL
+        let system_program = next_account_info(account_info_iter)?; //system program
+        let vault_quote = next_account_info(account_info_iter)?; // quote account of the nft vault
+        let holder_quote = next_account_info(account_info_iter)?; // quote account of the holder
+
+        assert_signer(holder)?;
+        assert_writable(holder)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_writable(nft_vault)?;
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_writable(spl_token_mint)?;
+        assert_associated_token(holder_token_account, holder.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(holder_token_account)?;
+        assert_pda(purchase_info, PURCHASEPREFIX, holder.key, pda.key, program_id, TokenError::InvalidPurchase)?;
+        assert_writable(purchase_info)?;
+        let purchase = Purchase::load(purchase_info, program_id)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_system_program(system_program)?;
+        assert_token_program(quote_token_program, &escrow.quote_token_program)?;
+        let quote = Quote::new(escrow.quote_mint, quote_token_program, system_program);
+        quote.assert_account(vault_quote, nft_vault.key)?;
+        quote.assert_account(holder_quote, holder.key)?;
+
+        Ok(Self{
+            holder,
+            nft_owner,
+            pda,
+            nft_vault,
+            spl_token_mint,
+            holder_token_account,
+            purchase_info,
+            token_program_id,
+            system_program,
+            vault_quote,
+            holder_quote,
+            escrow,
+            purchase,
+            quote,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Accounts of ReclaimCrowdfundNft
+pub struct ReclaimCrowdfundNftAccounts<'a, 'b: 'a> {
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub nft_associated_address: &'a AccountInfo<'b>,
+    pub nft_owner_nft_associated: &'a AccountInfo<'b>,
+    pub nft_mint: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> ReclaimCrowdfundNftAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator, receives the nft back
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let nft_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and nft mint
+        let nft_owner_nft_associated = next_account_info(account_info_iter)?; // associated address from nft owner and nft mint
+        let nft_mint = next_account_info(account_info_iter)?; // mint address of nft
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the nft mint
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+
+        assert_signer(nft_owner)?;
+        assert_writable(nft_owner)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        if escrow.nft_mint != *nft_mint.key
+        {
+            return Err(TokenError::InvalidMint.into());
+        }
+        assert_associated_token(nft_associated_address, nft_vault.key, nft_mint.key, &escrow.nft_token_program)?;
+        assert_writable(nft_associated_address)?;
+        assert_associated_token(nft_owner_nft_associated, nft_owner.key, nft_mint.key, &escrow.nft_token_program)?;
+        assert_writable(nft_owner_nft_associated)?;
+        assert_token_program(token_program_id, &escrow.nft_token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+
+        Ok(Self{
+            nft_owner,
+            pda,
+            nft_vault,
+            nft_associated_address,
+            nft_owner_nft_associated,
+            nft_mint,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            escrow,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Accounts of SettleUnsold, anyone can settle the unsold fractions once the buy period ended
+pub struct SettleUnsoldAccounts<'a, 'b: 'a> {
+    pub payer: &'a AccountInfo<'b>,
+    pub nft_owner: &'a AccountInfo<'b>,
+    pub pda: &'a AccountInfo<'b>,
+    pub nft_vault: &'a AccountInfo<'b>,
+    pub spl_token_mint: &'a AccountInfo<'b>,
+    pub spl_vault_associated_address: &'a AccountInfo<'b>,
+    pub owner_token_account: &'a AccountInfo<'b>,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub associated_token_info: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub escrow: NftDetails,
+    pub nft_vault_bump: u8,
+}
+impl<'a, 'b: 'a> SettleUnsoldAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?; // sender or signer, pays for the owner token account
+        let nft_owner = next_account_info(account_info_iter)?; // auction creator
+        let pda = next_account_info(account_info_iter)?; // main data account
+        let nft_vault = next_account_info(account_info_iter)?; // nft vault
+        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
+        let spl_vault_associated_address = next_account_info(account_info_iter)?; // associated address from nft vault and spl token mint
+        let owner_token_account = next_account_info(account_info_iter)?; // associated address from nft owner and spl token mint
+        let token_program_id = next_account_info(account_info_iter)?; // token program of the fraction mint, spl_token or spl_token_2022
+        let associated_token_info = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+
+        assert_signer(payer)?;
+        assert_writable(payer)?;
+        assert_writable(pda)?;
+        let escrow = NftDetails::load(pda, program_id)?;
+        if escrow.nft_owner != *nft_owner.key
+        {
+            return Err(TokenError::InvalidNftOwner.into());
+        }
+        let nft_vault_bump = assert_pda(nft_vault, NFTPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidVault)?;
+        assert_pda(spl_token_mint, SPLTOKENPREFIX, nft_owner.key, pda.key, program_id, TokenError::InvalidMint)?;
+        assert_writable(spl_token_mint)?;
+        assert_associated_token(spl_vault_associated_address, nft_vault.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(spl_vault_associated_address)?;
+        assert_associated_token(owner_token_account, nft_owner.key, spl_token_mint.key, &escrow.token_program)?;
+        assert_writable(owner_token_account)?;
+        assert_token_program(token_program_id, &escrow.token_program)?;
+        assert_associated_token_program(associated_token_info)?;
+        assert_system_program(system_program)?;
+        assert_rent_sysvar(rent_info)?;
+
+        Ok(Self{
+            payer,
+            nft_owner,
+            pda,
+            nft_vault,
+            spl_token_mint,
+            spl_vault_associated_address,
+            owner_token_account,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            escrow,
+            nft_vault_bump,
+        })
+    }
+}
+
+/// Extra accounts of the transfer hook Execute, the holder records of the source and destination owners
+pub fn holder_account_metas() -> Result<Vec<ExtraAccountMeta>, ProgramError> {
+    let holder_record_of = |token_account_index: u8| ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal{bytes: HOLDERPREFIX.as_bytes().to_vec()},
+            Seed::AccountKey{index: 1},
+            // owner field of the token account
+            Seed::AccountData{account_index: token_account_index, data_index: 32, length: 32},
+        ],
+        false,
+        true,
+    );
+    Ok(vec![holder_record_of(0)?, holder_record_of(2)?])
+}
+
+/// Accounts of InitHolderAccountMetas, called by the vault while it is still the mint authority
+pub struct InitHolderAccountMetasAccounts<'a, 'b: 'a> {
+    pub extra_account_metas: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub mint_authority: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub extra_account_metas_bump: u8,
+}
+impl<'a, 'b: 'a> InitHolderAccountMetasAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let extra_account_metas = next_account_info(account_info_iter)?; // validation pda of the mint, funded with its rent
+        let mint = next_account_info(account_info_iter)?; // spl token mint
+        let mint_authority = next_account_info(account_info_iter)?; // nft vault
+        let system_program = next_account_info(account_info_iter)?; //system program
+
+        assert_writable(extra_account_metas)?;
+        let (address, extra_account_metas_bump) = get_extra_account_metas_address_and_bump_seed(mint.key, program_id);
+        if address != *extra_account_metas.key
+        {
+            return Err(TokenError::InvalidTransferHook.into());
+        }
+        if !extra_account_metas.data_is_empty()
+        {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        assert_owned_by(mint, &spl_token_2022::id())?;
+        assert_transfer_hook(mint, program_id)?;
+        assert_signer(mint_authority)?;
+        if get_mint_authority(mint)? != Some(*mint_authority.key)
+        {
+            return Err(TokenError::InvalidAuthority.into());
+        }
+        assert_system_program(system_program)?;
+
+        Ok(Self{
+            extra_account_metas,
+            mint,
+            mint_authority,
+            system_program,
+            extra_account_metas_bump,
+        })
+    }
+}
+
+/// Accounts of TransferHookExecute, in the order Token-2022 passes them
+pub struct TransferHookExecuteAccounts<'a, 'b: 'a> {
+    pub source: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub destination: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub extra_account_metas: &'a AccountInfo<'b>,
+    pub source_record: &'a AccountInfo<'b>,
+    pub destination_record: &'a AccountInfo<'b>,
+}
+impl<'a, 'b: 'a> TransferHookExecuteAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], amount: u64) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let source = next_account_info(account_info_iter)?; // source token account
+        let mint = next_account_info(account_info_iter)?; // spl token mint
+        let destination = next_account_info(account_info_iter)?; // destination token account
+        let authority = next_account_info(account_info_iter)?; // owner or delegate of the source
+        let extra_account_metas = next_account_info(account_info_iter)?; // validation pda of the mint
+        let source_record = next_account_info(account_info_iter)?; // holder record of the source owner, may be uninitialized
+        let destination_record = next_account_info(account_info_iter)?; // holder record of the destination owner, may be uninitialized
+
+        assert_owned_by(mint, &spl_token_2022::id())?;
+        assert_transfer_hook(mint, program_id)?;
+        assert_owned_by(source, &spl_token_2022::id())?;
+        assert_transferring(source)?;
+        assert_owned_by(destination, &spl_token_2022::id())?;
+        if get_extra_account_metas_address(mint.key, program_id) != *extra_account_metas.key
+        {
+            return Err(TokenError::InvalidTransferHook.into());
+        }
+        assert_owned_by(extra_account_metas, program_id)?;
+        //the holder records must be the pdas of the mint and the owners of both token accounts
+        ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+            accounts,
+            &TransferHookInstruction::Execute{amount}.pack(),
+            program_id,
+            &extra_account_metas.try_borrow_data()?,
+        )?;
+
+        Ok(Self{
+            source,
+            mint,
+            destination,
+            authority,
+            extra_account_metas,
+            source_record,
+            destination_record,
+        })
+    }
+}
+
+/// Accounts of SyncHolder, sent to the transfer hook program of the fraction mint
+pub struct SyncHolderAccounts<'a, 'b: 'a> {
+    pub payer: &'a AccountInfo<'b>,
+    pub holder_record: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub holder_record_bump: u8,
+}
+impl<'a, 'b: 'a> SyncHolderAccounts<'a, 'b> {
+    pub fn validate(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?; // sender or signer, pays for a new record
+        let holder_record = next_account_info(account_info_iter)?; // holder record from HOLDERPREFIX, spl token mint and owner
+        let owner = next_account_info(account_info_iter)?; // wallet holding the fractions
+        let token_account = next_account_info(account_info_iter)?; // associated address from owner and spl token mint
+        let mint = next_account_info(account_info_iter)?; // spl token mint
+        let system_program = next_account_info(account_info_iter)?; //system program
+
+        assert_signer(payer)?;
+        assert_writable(payer)?;
+        assert_owned_by(mint, &spl_token_2022::id())?;
+        assert_transfer_hook(mint, program_id)?;
+        let holder_record_bump = assert_pda(holder_record, HOLDERPREFIX, mint.key, owner.key, program_id, TokenError::InvalidHolderRecord)?;
+        assert_writable(holder_record)?;
+        assert_associated_token(token_account, owner.key, mint.key, &spl_token_2022::id())?;
+        if !token_account.data_is_empty()
+        {
+            assert_owned_by(token_account, &spl_token_2022::id())?;
+        }
+        assert_system_program(system_program)?;
+
+        Ok(Self{
+            payer,
+            holder_record,
+            owner,
+            token_account,
+            mint,
+            system_program,
+            holder_record_bump,
+        })
+    }
+}
+
+/// Loads the holder record of owner, kept by the transfer hook program of the fraction mint
+pub fn load_holder_record(holder_record: &AccountInfo, mint: &AccountInfo, owner: &Pubkey) -> Result<HolderRecord, ProgramError> {
+    let hook_program = get_transfer_hook_program(mint)?.ok_or(TokenError::InvalidTransferHook)?;
+    assert_pda(holder_record, HOLDERPREFIX, mint.key, owner, &hook_program, TokenError::InvalidHolderRecord)?;
+    HolderRecord::load(holder_record, &hook_program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::AccountType,
+        test_utils::{account_infos, install_stubs, TestAccount},
+        utils::generate_pda_and_bump_seed,
+    };
+    use borsh::BorshSerialize;
+    use solana_program::{rent::Rent, system_program, sysvar};
+    use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+    /// Valid accounts of a Deposit priced in SOL, in instruction order
+    fn deposit_accounts(program_id: &Pubkey, nft_owner: Pubkey, pda: Pubkey, token_program: Pubkey) -> Vec<TestAccount> {
+        let nft_mint = Pubkey::new_unique();
+        let (spl_token_mint, _) = generate_pda_and_bump_seed(SPLTOKENPREFIX, &nft_owner, &pda, program_id);
+        let (nft_vault, _) = generate_pda_and_bump_seed(NFTPREFIX, &nft_owner, &pda, program_id);
+        let ata = |wallet: &Pubkey, mint: &Pubkey| get_associated_token_address_with_program_id(wallet, mint, &token_program);
+        vec![
+            TestAccount::new(nft_owner, system_program::id(), 1_000_000_000, vec![]).signer(),
+            TestAccount::new(token_program, Pubkey::default(), 1, vec![]).readonly(),
+            TestAccount::new(token_program, Pubkey::default(), 1, vec![]).readonly(),
+            TestAccount::new(pda, system_program::id(), 0, vec![]).signer(),
+            TestAccount::new(spl_token_mint, system_program::id(), 0, vec![]),
+            TestAccount::new(ata(&nft_owner, &spl_token_mint), system_program::id(), 0, vec![]),
+            TestAccount::new(nft_mint, token_program, 1, vec![]).readonly(),
+            TestAccount::new(nft_vault, system_program::id(), 0, vec![]),
+            TestAccount::new(ata(&nft_vault, &nft_mint), system_program::id(), 0, vec![]),
+            TestAccount::new(ata(&nft_vault, &spl_token_mint), system_program::id(), 0, vec![]),
+            TestAccount::new(Pubkey::new_unique(), system_program::id(), 0, vec![]),
+            TestAccount::new(spl_associated_token_account::id(), Pubkey::default(), 1, vec![]).readonly(),
+            TestAccount::new(ata(&nft_owner, &nft_mint), token_program, 1, vec![]),
+            TestAccount::new(sysvar::rent::id(), sysvar::id(), 1, vec![]).readonly(),
+            TestAccount::new(system_program::id(), Pubkey::default(), 1, vec![]).readonly(),
+        ]
+    }
+
+    /// Validates the deposit accounts with the account at index replaced by an unrelated key
+    fn validate_substituted(index: usize) -> Result<(), ProgramError> {
+        install_stubs();
+        let program_id = Pubkey::new_unique();
+        let mut accounts = deposit_accounts(&program_id, Pubkey::new_unique(), Pubkey::new_unique(), spl_token::id());
+        accounts[index].key = Pubkey::new_unique();
+        let accounts = account_infos(&accounts);
+        DepositAccounts::validate(&program_id, &accounts, &Pubkey::default(), None).map(|_| ())
+    }
+
+    #[test]
+    fn deposit_accepts_the_real_programs() {
+        install_stubs();
+        let program_id = Pubkey::new_unique();
+        let accounts = account_infos(&deposit_accounts(&program_id, Pubkey::new_unique(), Pubkey::new_unique(), spl_token::id()));
+        assert!(DepositAccounts::validate(&program_id, &accounts, &Pubkey::default(), None).is_ok());
+    }
+
+    #[test]
+    fn deposit_prices_a_token_2022_vault_in_a_legacy_quote_mint() {
+        install_stubs();
+        let program_id = Pubkey::new_unique();
+        let nft_owner = Pubkey::new_unique();
+        let pda = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let (nft_vault, _) = generate_pda_and_bump_seed(NFTPREFIX, &nft_owner, &pda, &program_id);
+        let (proceeds, _) = generate_pda_and_bump_seed(PROCEEDSPREFIX, &nft_owner, &pda, &program_id);
+        let mut accounts = deposit_accounts(&program_id, nft_owner, pda, spl_token_2022::id());
+        accounts[2].key = spl_token::id();
+        accounts.push(TestAccount::new(proceeds, system_program::id(), 0, vec![]));
+        accounts.push(TestAccount::new(quote_mint, spl_token::id(), 1, vec![]).readonly());
+        accounts.push(TestAccount::new(get_associated_token_address_with_program_id(&nft_vault, &quote_mint, &spl_token::id()), system_program::id(), 0, vec![]));
+        let infos = account_infos(&accounts);
+        let deposit = DepositAccounts::validate(&program_id, &infos, &quote_mint, None).unwrap();
+        assert_eq!(*deposit.token_program_id.key, spl_token_2022::id());
+        assert_eq!(*deposit.quote_token_program.key, spl_token::id());
+
+        // the quote account has to be derived with the quote token program
+        accounts[17].key = get_associated_token_address_with_program_id(&nft_vault, &quote_mint, &spl_token_2022::id());
+        let infos = account_infos(&accounts);
+        assert!(DepositAccounts::validate(&program_id, &infos, &quote_mint, None).is_err());
+    }
+
+    #[test]
+    fn deposit_rejects_a_substituted_token_program() {
+        assert_eq!(validate_substituted(1), Err(TokenError::InvalidTokenProgram.into()));
+    }
+
+    #[test]
+    fn deposit_rejects_a_substituted_quote_token_program() {
+        assert_eq!(validate_substituted(2), Err(TokenError::InvalidTokenProgram.into()));
+    }
+
+    #[test]
+    fn deposit_rejects_a_substituted_associated_token_program() {
+        assert_eq!(validate_substituted(11), Err(TokenError::InvalidAssociatedTokenProgram.into()));
+    }
+
+    #[test]
+    fn deposit_rejects_a_substituted_rent_sysvar() {
+        assert_eq!(validate_substituted(13), Err(TokenError::InvalidRentSysvar.into()));
+    }
+
+    #[test]
+    fn deposit_rejects_a_substituted_system_program() {
+        assert_eq!(validate_substituted(14), Err(TokenError::InvalidSystemProgram.into()));
+    }
+
+    #[test]
+    fn auctions_are_kept_apart_per_vault() {
+        install_stubs();
+        let program_id = Pubkey::new_unique();
+        let nft_owner = Pubkey::new_unique();
+        let (vault_a, vault_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_ne!(
+            generate_auction_pda(&nft_owner, &vault_a, 3, &program_id),
+            generate_auction_pda(&nft_owner, &vault_b, 3, &program_id)
+        );
+        let auction = Auction{
+            account_type: AccountType::Auction,
+            version: Auction::VERSION,
+            vault: vault_a,
+            max_price: 10,
+            max_payer: Pubkey::new_unique(),
+            num_tokens: 5,
+            day: 3,
+        };
+        let rent = Rent::default().minimum_balance(Auction::LEN);
+        let accounts = account_infos(&[
+            TestAccount::new(Pubkey::new_unique(), program_id, rent, auction.try_to_vec().unwrap()),
+            TestAccount::new(vault_a, program_id, 1, vec![]),
+            TestAccount::new(vault_b, program_id, 1, vec![]),
+        ]);
+        assert_eq!(load_auction(&accounts[0], &accounts[1], &program_id).unwrap(), auction);
+        assert_eq!(load_auction(&accounts[0], &accounts[2], &program_id), Err(TokenError::InvalidAuction.into()));
+    }
+}
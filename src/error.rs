@@ -5,29 +5,174 @@ use solana_program::{decode_error::DecodeError, program_error::ProgramError};
 use thiserror::Error;
 
 /// Errors that may be returned by the Token program.
+///
+/// The discriminants are the `ProgramError::Custom` codes seen by clients,
+/// new variants are only ever appended.
 #[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
 pub enum TokenError {
     // 0
     /// Lamport balance below rent-exempt threshold.
     #[error("Lamport balance below rent-exempt threshold")]
-    NotRentExempt,
+    NotRentExempt = 0,
     // invalid instruction
     #[error("Invalid instruction")]
-    InvalidInstruction,
-     // auction ended 
+    InvalidInstruction = 1,
+     // auction ended
      #[error("auction ended")]
-     AuctionEnded,
+     AuctionEnded = 2,
      // Overflow
      #[error("Token overflow")]
-     Overflow,
+     Overflow = 3,
      #[error("Not started")]
-    Notstarted,
+    Notstarted = 4,
+    // 5
     #[error("Token Finished")]
-    TokenFinished,
+    TokenFinished = 5,
     #[error("Buy Period Ended")]
-    AuctionStarted,
+    AuctionStarted = 6,
     #[error("Price is Lower")]
-    PriceLower,
+    PriceLower = 7,
+    #[error("Account type mismatch")]
+    InvalidAccountType = 8,
+    #[error("Account version mismatch")]
+    InvalidAccountVersion = 9,
+    // 10
+    #[error("Invalid token program")]
+    InvalidTokenProgram = 10,
+    #[error("Invalid associated token program")]
+    InvalidAssociatedTokenProgram = 11,
+    #[error("Invalid system program")]
+    InvalidSystemProgram = 12,
+    #[error("Invalid rent sysvar")]
+    InvalidRentSysvar = 13,
+    /// Nft vault is not the pda of the nft owner and data account
+    #[error("Invalid vault")]
+    InvalidVault = 14,
+    // 15
+    /// Fraction mint is not the pda of the nft owner and data account
+    #[error("Invalid mint")]
+    InvalidMint = 15,
+    /// Token account is not the associated token account of the expected owner and mint
+    #[error("Invalid token account")]
+    InvalidTokenAccount = 16,
+    #[error("Nft owner doesn't match the vault")]
+    InvalidNftOwner = 17,
+    #[error("Invalid auction account")]
+    InvalidAuction = 18,
+    #[error("Previous highest bidder doesn't match")]
+    InvalidPreviousBidder = 19,
+    // 20
+    #[error("Account is not writable")]
+    AccountNotWritable = 20,
+    /// Not enough fractions held for the coin flip
+    #[error("Insufficient stake")]
+    InsufficientStake = 21,
+    #[error("Wrong auction day")]
+    WrongAuctionDay = 22,
+    #[error("Already claimed")]
+    AlreadyClaimed = 23,
+    #[error("Not the auction winner")]
+    NotAuctionWinner = 24,
+    // 25
+    #[error("Not the coin flip winner")]
+    NotCoinFlipWinner = 25,
+    /// Clock is before the epoch or earlier than the vault creation time
+    #[error("Invalid clock")]
+    InvalidClock = 26,
+    /// Config account is not the config pda
+    #[error("Invalid config")]
+    InvalidConfig = 27,
+    #[error("Invalid treasury")]
+    InvalidTreasury = 28,
+    #[error("Fee above 100%")]
+    InvalidFee = 29,
+    // 30
+    /// Signer is not the config or program upgrade authority
+    #[error("Invalid authority")]
+    InvalidAuthority = 30,
+    /// Metadata account is not the Metaplex metadata of the nft
+    #[error("Invalid metadata")]
+    InvalidMetadata = 31,
+    /// A verified creator account is missing from the trailing accounts
+    #[error("Invalid creator")]
+    InvalidCreator = 32,
+    /// Beneficiary list is empty, too long, has duplicates or zero shares
+    #[error("Invalid beneficiaries")]
+    InvalidBeneficiaries = 33,
+    #[error("Invalid proceeds account")]
+    InvalidProceeds = 34,
+    // 35
+    #[error("Not a beneficiary of the vault")]
+    NotBeneficiary = 35,
+    #[error("Invalid stake pool")]
+    InvalidStakePool = 36,
+    /// Stake entry is not the pda of the staker and stake pool
+    #[error("Invalid stake entry")]
+    InvalidStakeEntry = 37,
+    /// Proposed interval is zero or inflation is above 100%
+    #[error("Invalid proposal")]
+    InvalidProposal = 38,
+    #[error("Voting is closed")]
+    VotingClosed = 39,
+    // 40
+    /// Voting or the timelock of the proposal is still running
+    #[error("Proposal is not executable yet")]
+    TimelockActive = 40,
+    #[error("Proposal didn't pass")]
+    ProposalNotPassed = 41,
+    #[error("Proposal already executed")]
+    ProposalExecuted = 42,
+    /// Vote account is not the pda of the voter and proposal or was already cast
+    #[error("Invalid vote")]
+    InvalidVote = 43,
+    #[error("Invalid buyout")]
+    InvalidBuyout = 44,
+    // 45
+    #[error("Buyout below the implied valuation")]
+    BuyoutBelowValuation = 45,
+    /// Buyout was accepted, refunded or its deadline passed
+    #[error("Buyout is closed")]
+    BuyoutClosed = 46,
+    /// Buyout is still open or didn't reach its threshold
+    #[error("Buyout is still open")]
+    BuyoutOpen = 47,
+    #[error("Vault was bought out")]
+    VaultBoughtOut = 48,
+    /// Mint has no transfer hook or it points to another program
+    #[error("Invalid transfer hook")]
+    InvalidTransferHook = 49,
+    // 50
+    /// Holder record is not the pda of the mint and owner
+    #[error("Invalid holder record")]
+    InvalidHolderRecord = 50,
+    /// Fractions weren't held for the minimum holding period of the vault
+    #[error("Holding period not met")]
+    HoldingPeriodNotMet = 51,
+    /// Purchase account is not the pda of the buyer and vault
+    #[error("Invalid purchase")]
+    InvalidPurchase = 52,
+    /// Buyer and cap are not in the allowlist of the vault
+    #[error("Buyer is not allowlisted")]
+    NotAllowlisted = 53,
+    #[error("Allowlist cap exceeded")]
+    AllowanceExceeded = 54,
+    // 55
+    #[error("Wallet cap exceeded")]
+    WalletCapExceeded = 55,
+    #[error("Invalid sale mode")]
+    InvalidSaleMode = 56,
+    /// The primary sale is still running
+    #[error("Sale is still open")]
+    SaleOpen = 57,
+    /// The crowdfund ended short of its minimum raise
+    #[error("Minimum raise not reached")]
+    RaiseFailed = 58,
+    #[error("Minimum raise reached")]
+    RaiseReached = 59,
+    // 60
+    /// Auctions or coin flips are still to be claimed
+    #[error("Auctions or coin flips are still to be claimed")]
+    ClaimsPending = 60,
 }
 impl From<TokenError> for ProgramError {
     fn from(e: TokenError) -> Self {
@@ -38,4 +183,4 @@ impl<T> DecodeError<T> for TokenError {
     fn type_of() -> &'static str {
         "TokenError"
     }
-}
\ No newline at end of file
+}
@@ -1,22 +1,56 @@
 //! Instruction types
 use solana_program::{
     program_error::ProgramError,
+    pubkey::Pubkey,
     msg
 };
 
 
+use spl_tlv_account_resolution::account::ExtraAccountMeta;
+use spl_transfer_hook_interface::instruction::TransferHookInstruction;
 use crate::{
     error::TokenError,
+    state::SaleMode,
 };
-use std::convert::TryInto;
+use std::convert::{TryFrom,TryInto};
 
 pub struct ProcessDeposit{
     pub number_of_tokens: u64,
     pub price: u64,
+    /// Optional beneficiaries and their share weights, empty pays the nft owner
+    pub beneficiaries: Vec<(Pubkey, u16)>,
+    /// Optional SPL mint the vault is priced in, default is native SOL
+    pub quote_mint: Pubkey,
+    /// Optional transfer fee basis points and maximum fee of a Token-2022 fraction mint
+    pub transfer_fee: Option<(u16, u64)>,
+    /// Optional metadata account a Token-2022 fraction mint points to
+    pub metadata_address: Option<Pubkey>,
+    /// Optional transfer hook program of a Token-2022 fraction mint that keeps the holder records
+    pub transfer_hook: Option<Pubkey>,
+    /// Optional seconds the fractions have to be held before a coin flip
+    pub min_holding_period: u64,
+    /// Optional merkle root of the allowlisted buyers and their caps
+    pub allowlist_root: [u8; 32],
+    /// Optional fractions a single wallet can buy in the primary sale
+    pub max_per_wallet: u64,
+    /// Optional pricing of the primary sale, fixed by default
+    pub sale_mode: SaleMode,
+    /// Start price of a dutch sale
+    pub start_price: u64,
+    /// Dutch sale buyers are refunded down to the clearing price
+    pub sale_refunds: bool,
+    /// Slope or growth in basis points of a bonding curve sale
+    pub curve_param: u64,
+    /// Crowdfund target in the quote currency, the sale is escrowed until it is reached
+    pub min_raise: u64,
+    /// Unsold fractions are burned instead of handed to the curator after the sale
+    pub burn_unsold: bool,
 }
 pub struct ProcessBuy{
     pub token: u64,
     pub price: u64,
+    /// Cap of the buyer in the allowlist and the merkle proof of its leaf, required when the vault has an allowlist
+    pub allowance: Option<(u64, Vec<[u8; 32]>)>,
 }
 pub struct ProcessBuy2{
     pub day:u64,
@@ -25,35 +59,238 @@ pub struct ProcessBuy2{
 pub struct ProcessAuction1{
     pub price: u64,
 }
+pub struct InitConfig{
+    pub fee_bps: u16,
+    pub treasury: Pubkey,
+}
+pub struct UpdateConfig{
+    pub fee_bps: u16,
+    pub treasury: Pubkey,
+    pub authority: Pubkey,
+}
+pub struct Stake{
+    pub amount: u64,
+}
+pub struct Unstake{
+    pub amount: u64,
+}
+pub struct CreateProposal{
+    pub reserve_price: u64,
+    pub auction_interval: u64,
+    pub inflation_bps: u16,
+}
+pub struct CastVote{
+    pub amount: u64,
+    pub support: bool,
+}
+pub struct CreateBuyout{
+    pub price: u64,
+}
+pub struct CastBuyoutVote{
+    pub amount: u64,
+}
+pub struct RedeemBuyout{
+    pub amount: u64,
+}
+pub struct SellToCurve{
+    pub amount: u64,
+    /// Lowest accepted payout, protects the seller from earlier trades on the curve
+    pub min_payout: u64,
+}
+pub struct QuoteSale{
+    pub amount: u64,
+    /// Quote a sell back instead of a purchase
+    pub sell: bool,
+}
+pub struct RefundCrowdfund{
+    pub amount: u64,
+}
+pub struct TransferHookExecute{
+    pub amount: u64,
+}
+pub struct InitHolderAccountMetas{
+    pub extra_account_metas: Vec<ExtraAccountMeta>,
+}
 pub enum TokenInstruction {
     ProcessDeposit(ProcessDeposit),
     ProcessBuy(ProcessBuy),
     ProcessBuy2(ProcessBuy2),
     ProcessCoinFlip,
     ProcessClaimCoinFlip,
-    ProcessAuction1(ProcessAuction1)
+    ProcessAuction1(ProcessAuction1),
+    MigrateVault,
+    InitConfig(InitConfig),
+    UpdateConfig(UpdateConfig),
+    WithdrawProceeds,
+    InitStakePool,
+    Stake(Stake),
+    Unstake(Unstake),
+    ClaimStakeRewards,
+    CreateProposal(CreateProposal),
+    CastVote(CastVote),
+    ExecuteProposal,
+    WithdrawVote,
+    CreateBuyout(CreateBuyout),
+    CastBuyoutVote(CastBuyoutVote),
+    ExecuteBuyout,
+    RefundBuyout,
+    RedeemBuyout(RedeemBuyout),
+    SyncHolder,
+    ClaimSaleRefund,
+    SellToCurve(SellToCurve),
+    /// Returns the cost or payout of a primary sale trade as return data
+    QuoteSale(QuoteSale),
+    WithdrawSaleReserve,
+    RefundCrowdfund(RefundCrowdfund),
+    ReclaimCrowdfundNft,
+    /// Hands the unsold fractions to the curator or burns them once the buy period ended
+    SettleUnsold,
+    /// Execute of the transfer hook interface, called by Token-2022 on every transfer of a hooked mint
+    TransferHookExecute(TransferHookExecute),
+    /// InitializeExtraAccountMetaList of the transfer hook interface
+    InitHolderAccountMetas(InitHolderAccountMetas),
 }
 impl TokenInstruction {
     /// Unpacks a byte buffer into a [TokenInstruction](enum.TokenInstruction.html).
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
         use TokenError::InvalidInstruction;
+        // transfer hook interface instructions start with an 8 byte discriminator, whose first byte is no tag
+        match TransferHookInstruction::unpack(input) {
+            Ok(TransferHookInstruction::Execute{amount}) => {
+                return Ok(Self::TransferHookExecute(TransferHookExecute{amount}));
+            }
+            Ok(TransferHookInstruction::InitializeExtraAccountMetaList{extra_account_metas}) => {
+                return Ok(Self::InitHolderAccountMetas(InitHolderAccountMetas{extra_account_metas}));
+            }
+            Ok(TransferHookInstruction::UpdateExtraAccountMetaList{..}) => return Err(InvalidInstruction.into()),
+            Err(_) => {}
+        }
         let (&tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
         msg!("{:?}",input);
         Ok(match tag {
             // Initialize deposit NFT instruction 
             0 => {
                 let (number_of_tokens, rest) = rest.split_at(8);
-                let (price, _rest) = rest.split_at(8);
+                let (price, rest) = rest.split_at(8);
                 let number_of_tokens = number_of_tokens.try_into().map(u64::from_le_bytes).or(Err(InvalidInstruction))?;
                 let price = price.try_into().map(u64::from_le_bytes).or(Err(InvalidInstruction))?;
-                Self::ProcessDeposit(ProcessDeposit{number_of_tokens,price})
+                let mut beneficiaries = vec![];
+                let mut quote_mint = Pubkey::default();
+                let mut transfer_fee = None;
+                let mut metadata_address = None;
+                let mut transfer_hook = None;
+                let mut min_holding_period = 0;
+                let mut allowlist_root = [0; 32];
+                let mut max_per_wallet = 0;
+                let mut sale_mode = SaleMode::Fixed;
+                let mut start_price = 0;
+                let mut sale_refunds = false;
+                let mut curve_param = 0;
+                let mut min_raise = 0;
+                let mut burn_unsold = false;
+                if !rest.is_empty() {
+                    let (count, mut rest) = Self::unpack_u8(rest)?;
+                    for _ in 0..count {
+                        let (address, next) = Self::unpack_pubkey(rest)?;
+                        let (share, next) = Self::unpack_u16(next)?;
+                        beneficiaries.push((address, share));
+                        rest = next;
+                    }
+                    if !rest.is_empty() {
+                        let (mint, next) = Self::unpack_pubkey(rest)?;
+                        quote_mint = mint;
+                        rest = next;
+                    }
+                    // fraction mint extensions, bit 0 transfer fee, bit 1 metadata pointer and bit 2 transfer hook
+                    if !rest.is_empty() {
+                        let (extensions, next) = Self::unpack_u8(rest)?;
+                        rest = next;
+                        if extensions & 1 != 0 {
+                            let (fee_bps, next) = Self::unpack_u16(rest)?;
+                            let (maximum_fee, next) = Self::unpack_u64(next)?;
+                            transfer_fee = Some((fee_bps, maximum_fee));
+                            rest = next;
+                        }
+                        if extensions & 2 != 0 {
+                            let (address, next) = Self::unpack_pubkey(rest)?;
+                            metadata_address = Some(address);
+                            rest = next;
+                        }
+                        if extensions & 4 != 0 {
+                            let (program, next) = Self::unpack_pubkey(rest)?;
+                            transfer_hook = Some(program);
+                            rest = next;
+                        }
+                    }
+                    if !rest.is_empty() {
+                        let (period, next) = Self::unpack_u64(rest)?;
+                        min_holding_period = period;
+                        rest = next;
+                    }
+                    if !rest.is_empty() {
+                        let (root, next) = Self::unpack_hash(rest)?;
+                        allowlist_root = root;
+                        rest = next;
+                    }
+                    if !rest.is_empty() {
+                        let (cap, next) = Self::unpack_u64(rest)?;
+                        max_per_wallet = cap;
+                        rest = next;
+                    }
+                    // sale mode, 0 fixed, 1 dutch followed by its start price and refund flag,
+                    // 2 linear and 3 exponential curve followed by the curve parameter
+                    if !rest.is_empty() {
+                        let (mode, next) = Self::unpack_u8(rest)?;
+                        rest = next;
+                        match mode {
+                            0 => {}
+                            1 => {
+                                let (price, next) = Self::unpack_u64(rest)?;
+                                let (refunds, next) = Self::unpack_u8(next)?;
+                                rest = next;
+                                sale_mode = SaleMode::Dutch;
+                                start_price = price;
+                                sale_refunds = refunds != 0;
+                            }
+                            2 | 3 => {
+                                let (param, next) = Self::unpack_u64(rest)?;
+                                rest = next;
+                                sale_mode = if mode == 2 { SaleMode::LinearCurve } else { SaleMode::ExponentialCurve };
+                                curve_param = param;
+                            }
+                            _ => return Err(InvalidInstruction.into()),
+                        }
+                    }
+                    if !rest.is_empty() {
+                        let (raise, next) = Self::unpack_u64(rest)?;
+                        min_raise = raise;
+                        rest = next;
+                    }
+                    if !rest.is_empty() {
+                        let (burn, _rest) = Self::unpack_u8(rest)?;
+                        burn_unsold = burn != 0;
+                    }
+                }
+                Self::ProcessDeposit(ProcessDeposit{number_of_tokens,price,beneficiaries,quote_mint,transfer_fee,metadata_address,transfer_hook,min_holding_period,allowlist_root,max_per_wallet,sale_mode,start_price,sale_refunds,curve_param,min_raise,burn_unsold})
             }
             1 => {
                 let (token, rest) = rest.split_at(8);
                 let token = token.try_into().map(u64::from_le_bytes).or(Err(InvalidInstruction))?;
-                let (price, _rest) = rest.split_at(8);
+                let (price, rest) = rest.split_at(8);
                 let price = price.try_into().map(u64::from_le_bytes).or(Err(InvalidInstruction))?;
-                Self::ProcessBuy(ProcessBuy{token,price})
+                let mut allowance = None;
+                if !rest.is_empty() {
+                    let (cap, rest) = Self::unpack_u64(rest)?;
+                    let (count, mut rest) = Self::unpack_u8(rest)?;
+                    let mut proof = Vec::with_capacity(count as usize);
+                    for _ in 0..count {
+                        let (node, next) = Self::unpack_hash(rest)?;
+                        proof.push(node);
+                        rest = next;
+                    }
+                    allowance = Some((cap, proof));
+                }
+                Self::ProcessBuy(ProcessBuy{token,price,allowance})
             }
             2 => {
                 let (day, _rest) = rest.split_at(8);
@@ -72,8 +309,139 @@ impl TokenInstruction {
                 let price = price.try_into().map(u64::from_le_bytes).or(Err(InvalidInstruction))?;
                 Self::ProcessAuction1(ProcessAuction1{price})
             }
+            6 => {
+                Self::MigrateVault
+            }
+            7 => {
+                let (fee_bps, rest) = Self::unpack_u16(rest)?;
+                let (treasury, _rest) = Self::unpack_pubkey(rest)?;
+                Self::InitConfig(InitConfig{fee_bps,treasury})
+            }
+            8 => {
+                let (fee_bps, rest) = Self::unpack_u16(rest)?;
+                let (treasury, rest) = Self::unpack_pubkey(rest)?;
+                let (authority, _rest) = Self::unpack_pubkey(rest)?;
+                Self::UpdateConfig(UpdateConfig{fee_bps,treasury,authority})
+            }
+            9 => {
+                Self::WithdrawProceeds
+            }
+            10 => {
+                Self::InitStakePool
+            }
+            11 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::Stake(Stake{amount})
+            }
+            12 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::Unstake(Unstake{amount})
+            }
+            13 => {
+                Self::ClaimStakeRewards
+            }
+            14 => {
+                let (reserve_price, rest) = Self::unpack_u64(rest)?;
+                let (auction_interval, rest) = Self::unpack_u64(rest)?;
+                let (inflation_bps, _rest) = Self::unpack_u16(rest)?;
+                Self::CreateProposal(CreateProposal{reserve_price,auction_interval,inflation_bps})
+            }
+            15 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (support, _rest) = Self::unpack_u8(rest)?;
+                Self::CastVote(CastVote{amount,support: support != 0})
+            }
+            16 => {
+                Self::ExecuteProposal
+            }
+            17 => {
+                Self::WithdrawVote
+            }
+            18 => {
+                let (price, _rest) = Self::unpack_u64(rest)?;
+                Self::CreateBuyout(CreateBuyout{price})
+            }
+            19 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::CastBuyoutVote(CastBuyoutVote{amount})
+            }
+            20 => {
+                Self::ExecuteBuyout
+            }
+            21 => {
+                Self::RefundBuyout
+            }
+            22 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::RedeemBuyout(RedeemBuyout{amount})
+            }
+            23 => {
+                Self::SyncHolder
+            }
+            24 => {
+                Self::ClaimSaleRefund
+            }
+            25 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (min_payout, _rest) = Self::unpack_u64(rest)?;
+                Self::SellToCurve(SellToCurve{amount,min_payout})
+            }
+            26 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (sell, _rest) = Self::unpack_u8(rest)?;
+                Self::QuoteSale(QuoteSale{amount,sell: sell != 0})
+            }
+            27 => {
+                Self::WithdrawSaleReserve
+            }
+            28 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::RefundCrowdfund(RefundCrowdfund{amount})
+            }
+            29 => {
+                Self::ReclaimCrowdfundNft
+            }
+            30 => {
+                Self::SettleUnsold
+            }
 
             _ => return Err(TokenError::InvalidInstruction.into()),
         })
     }
-}
\ No newline at end of file
+    fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        let (&value, rest) = input.split_first().ok_or(TokenError::InvalidInstruction)?;
+        Ok((value, rest))
+    }
+    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        if input.len() < 2 {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let (value, rest) = input.split_at(2);
+        let value = value.try_into().map(u16::from_le_bytes).or(Err(TokenError::InvalidInstruction))?;
+        Ok((value, rest))
+    }
+    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() < 8 {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let (value, rest) = input.split_at(8);
+        let value = value.try_into().map(u64::from_le_bytes).or(Err(TokenError::InvalidInstruction))?;
+        Ok((value, rest))
+    }
+    fn unpack_hash(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+        if input.len() < 32 {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let (hash, rest) = input.split_at(32);
+        let hash = hash.try_into().or(Err(TokenError::InvalidInstruction))?;
+        Ok((hash, rest))
+    }
+    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+        if input.len() < 32 {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let (key, rest) = input.split_at(32);
+        let key = Pubkey::try_from(key).or(Err(TokenError::InvalidInstruction))?;
+        Ok((key, rest))
+    }
+}
@@ -1,8 +1,13 @@
+pub mod accounts;
 pub mod processor;
+pub mod royalties;
+pub mod quote;
 pub mod error;
 pub mod instruction;
 pub mod utils;
 pub mod state;
+#[cfg(test)]
+mod test_utils;
 use crate::{
     processor::Processor,
     error::TokenError
@@ -15,6 +20,15 @@ use solana_program::{
 pub const SPLTOKENPREFIX: &str = "spl_token";
 pub const NFTPREFIX: &str = "nft";
 pub const AUCTIONPREFIX: &str ="ricks_auction";
+pub const CONFIGPREFIX: &str = "ricks_config";
+pub const METADATAPREFIX: &str = "metadata";
+pub const PROCEEDSPREFIX: &str = "ricks_proceeds";
+pub const STAKEPREFIX: &str = "ricks_stake";
+pub const STAKEENTRYPREFIX: &str = "ricks_stake_entry";
+pub const VOTEPREFIX: &str = "ricks_vote";
+pub const BUYOUTPREFIX: &str = "ricks_buyout";
+pub const HOLDERPREFIX: &str = "ricks_holder";
+pub const PURCHASEPREFIX: &str = "ricks_purchase";
 
 entrypoint!(process_instruction);
 fn process_instruction(
@@ -1,132 +1,239 @@
 use crate::{
+    accounts::{
+        DepositAccounts,
+        AuctionAccounts,
+        BuyAccounts,
+        SettleAccounts,
+        CoinFlipAccounts,
+        ClaimCoinFlipAccounts,
+        MigrateVaultAccounts,
+        InitConfigAccounts,
+        UpdateConfigAccounts,
+        WithdrawProceedsAccounts,
+        InitStakePoolAccounts,
+        StakeAccounts,
+        CreateProposalAccounts,
+        CastVoteAccounts,
+        ExecuteProposalAccounts,
+        WithdrawVoteAccounts,
+        CreateBuyoutAccounts,
+        BuyoutVoteAccounts,
+        ExecuteBuyoutAccounts,
+        RefundBuyoutAccounts,
+        RedeemBuyoutAccounts,
+        InitHolderAccountMetasAccounts,
+        TransferHookExecuteAccounts,
+        SyncHolderAccounts,
+        HoldingProof,
+        ClaimSaleRefundAccounts,
+        SellToCurveAccounts,
+        QuoteSaleAccounts,
+        WithdrawSaleReserveAccounts,
+        RefundCrowdfundAccounts,
+        ReclaimCrowdfundNftAccounts,
+        SettleUnsoldAccounts,
+        holder_account_metas,
+    },
     error::TokenError,
+    royalties::pay_royalties,
+    quote::Quote,
     instruction::{
         TokenInstruction,
         ProcessDeposit,
         ProcessBuy,
         ProcessBuy2,
         ProcessAuction1,
+        InitConfig,
+        UpdateConfig,
+        Stake,
+        Unstake,
+        CreateProposal,
+        CastVote,
+        CreateBuyout,
+        CastBuyoutVote,
+        RedeemBuyout,
+        SellToCurve,
+        QuoteSale,
+        RefundCrowdfund,
+        TransferHookExecute,
+        InitHolderAccountMetas,
+    },
+    utils::{
+        create_pda_account,
+        create_account_signed,
+        transfer_lamports,
+        get_token_balance,
+        get_mint_state,
+        transfer_tokens,
+        current_timestamp,
+        seconds_since,
+        allowlist_leaf,
+        verify_merkle_proof,
+        assert_rent_exempt,
+        assert_withdrawal_keeps_rent,
     },
-    utils::{generate_pda_and_bump_seed,create_pda_account,get_token_balance},
     SPLTOKENPREFIX,
     NFTPREFIX,
     AUCTIONPREFIX,
-    state::{NftDetails,CoinFlip,Auction}
+    CONFIGPREFIX,
+    PROCEEDSPREFIX,
+    STAKEPREFIX,
+    STAKEENTRYPREFIX,
+    VOTEPREFIX,
+    BUYOUTPREFIX,
+    HOLDERPREFIX,
+    PURCHASEPREFIX,
+    state::{
+        AccountType,
+        ProgramAccount,
+        NftDetails,
+        CoinFlip,
+        Auction,
+        Config,
+        Proceeds,
+        Beneficiary,
+        StakePool,
+        StakeEntry,
+        MAX_FEE_BPS,
+        MAX_BENEFICIARIES,
+        DEFAULT_AUCTION_INTERVAL,
+        DEFAULT_INFLATION_BPS,
+        Proposal,
+        Vote,
+        VOTING_PERIOD,
+        TIMELOCK,
+        QUORUM_BPS,
+        MAX_INFLATION_BPS,
+        Buyout,
+        BuyoutStatus,
+        BUYOUT_PERIOD,
+        
+        HolderRecord,
+        Checkpoint,
+        MAX_CHECKPOINTS,
+        Purchase,
+        SaleMode,
+    }
 };
-use borsh::{BorshDeserialize, BorshSerialize};
 
 use solana_program::{
-    account_info::{AccountInfo,next_account_info},
+    account_info::AccountInfo,
     program_error::{PrintProgramError,ProgramError},
     decode_error::DecodeError,
     entrypoint::ProgramResult,
-    program::{invoke,invoke_signed},
+    program::{invoke,invoke_signed,set_return_data},
     system_instruction,
     pubkey::Pubkey,
-    sysvar::{rent::Rent,Sysvar,clock::Clock},
+    sysvar::{rent::Rent,Sysvar},
     msg,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_token_2022::{
+    extension::{
+        ExtensionType,
+        metadata_pointer,
+        transfer_fee::instruction::initialize_transfer_fee_config,
+        transfer_hook,
+    },
+    state::Mint,
+};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta,state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::{
+    collect_extra_account_metas_signer_seeds,
+    instruction::{ExecuteInstruction,initialize_extra_account_meta_list},
+};
 use num_traits::FromPrimitive;
 /// Program state handler.
 pub struct Processor {}
 impl Processor {
-    pub fn process_deposit_nft(program_id: &Pubkey,accounts: &[AccountInfo],number_of_tokens: u64, price:u64)-> ProgramResult {
+    pub fn process_deposit_nft(program_id: &Pubkey,accounts: &[AccountInfo],deposit: ProcessDeposit)-> ProgramResult {
+        let ProcessDeposit{
+            number_of_tokens,
+            price,
+            beneficiaries,
+            quote_mint,
+            transfer_fee,
+            metadata_address,
+            transfer_hook,
+            min_holding_period,
+            allowlist_root,
+            max_per_wallet,
+            sale_mode,
+            start_price,
+            sale_refunds,
+            curve_param,
+            min_raise,
+            burn_unsold,
+        } = deposit;
         //depositing the NFT
-        let account_info_iter = &mut accounts.iter();
-        let nft_owner =  next_account_info(account_info_iter)?; // sender or signer
-        let token_program_id = next_account_info(account_info_iter)?; // TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA
-        let pda = next_account_info(account_info_iter)?; // pda data 
-        let spl_token_mint = next_account_info(account_info_iter)?;  // spl token address generated from SPLTOKENPREFIX, nft_owner, pda and program id
-        let _spl_associated_token = next_account_info(account_info_iter)?; // nft owner associated of spl_token_mint 
-        let nft_mint = next_account_info(account_info_iter)?;  // mint address of nft
-        let nft_vault = next_account_info(account_info_iter)?;  // nft vault address from NFTPREFIX, nft_owner, pda and program id
-        let nft_associated_address = next_account_info(account_info_iter)?; // address generated from nft_vault_address and nft mint address token account address
-        let spl_vault_associated_address = next_account_info(account_info_iter)?; // address generated from nft_vault_address and spl token mint address
-        let _nft_spl_owner_address = next_account_info(account_info_iter)?; // // nft/token vault address generated from spltoken mint, nft_owner, pda and program id
-        let associated_token_info = next_account_info(account_info_iter)?; // Associated token master {ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL}
-        let nft_owner_nft_associated = next_account_info(account_info_iter)?;  // nft owner nft id token account address
-        let rent_info  = next_account_info(account_info_iter)?; // rent 
-        let system_program = next_account_info(account_info_iter)?; //system program
-
-       //checking if the owner is the signer or not
-        if !nft_owner.is_signer
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        //finding nft token account
-        let nft_token_address=get_associated_token_address(nft_owner.key,nft_mint.key);
-
-        //verifying nft token address
-        if nft_token_address!=*nft_owner_nft_associated.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
+        let DepositAccounts{
+            nft_owner,
+            token_program_id,
+            quote_token_program,
+            pda,
+            spl_token_mint,
+            nft_mint,
+            nft_vault,
+            nft_associated_address,
+            spl_vault_associated_address,
+            associated_token_info,
+            nft_owner_nft_associated,
+            rent_info,
+            system_program,
+            proceeds_info,
+            quote_mint_info,
+            vault_quote,
+            proceeds_quote,
+            nft_token_program,
+            transfer_hook_program,
+            extra_account_metas,
+            nft_vault_bump,
+            spl_token_bump,
+            proceeds_bump,
+            ..
+        } = DepositAccounts::validate(program_id, accounts, &quote_mint, transfer_hook.as_ref())?;
+
+        if sale_mode == SaleMode::Dutch && start_price < price
+        {
+            msg!("A dutch sale has to start above its floor price {}",price);
+            return Err(TokenError::InvalidSaleMode.into());
         }
-        
-        // new token mint for minting
-        let (spl_token_address, bump_seed_spl) = generate_pda_and_bump_seed(
-            SPLTOKENPREFIX,
-            nft_owner.key,
-            pda.key,
-            program_id
-        );
-         // verifying
-        if spl_token_address!=*spl_token_mint.key
+        if sale_mode == SaleMode::ExponentialCurve && curve_param == 0
+        {
+            msg!("An exponential curve needs a growth above zero");
+            return Err(TokenError::InvalidSaleMode.into());
+        }
+        if min_raise > 0 && sale_refunds
         {
-            return Err(ProgramError::MissingRequiredSignature);
+            msg!("A crowdfund refunds its buyers in full, it can't also refund dutch premiums");
+            return Err(TokenError::InvalidSaleMode.into());
         }
+
          // signer seeds for spl_token_mint
         let spl_token_signer_seeds: &[&[_]] = &[
             SPLTOKENPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda.key.to_bytes(),
-            &[bump_seed_spl],
+            &[spl_token_bump],
         ];
-        //verifying spl mint token account
-        let spl_vault_associated_token =get_associated_token_address(nft_vault.key,spl_token_mint.key);
-        if spl_vault_associated_token!=*spl_vault_associated_address.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        //nft_vault where nft is stored
-        let (nft_vault_address, bump_seed) = generate_pda_and_bump_seed(
-            NFTPREFIX,
-            nft_owner.key,
-            pda.key,
-            program_id
-        );
-         //nft_vault where nft is stored
-        if nft_vault_address!=*nft_vault.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
         //signer seeds for nft_vault
         let nft_vault_signer_seeds: &[&[_]] = &[
             NFTPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda.key.to_bytes(),
-            &[bump_seed],
+            &[nft_vault_bump],
         ];
-         //finding nft token account
-         let vault_nft_token_address=get_associated_token_address(nft_vault.key,nft_mint.key);
-
-         //verifying nft token address
-         if vault_nft_token_address!=*nft_associated_address.key
-         {
-             return Err(ProgramError::MissingRequiredSignature);
-         }
-         
          //rent account
         let rent = Rent::get()?;
-        let transfer_amount =  rent.minimum_balance(std::mem::size_of::<NftDetails>());
+        let transfer_amount =  rent.minimum_balance(NftDetails::LEN);
         create_pda_account( 
             nft_owner,
             transfer_amount,
-            std::mem::size_of::<NftDetails>(),
+            NftDetails::LEN,
             program_id,
             system_program,
             pda         //Data ACCOUNT 
         )?;
+        assert_rent_exempt(pda, &rent)?;
         invoke(
             &system_instruction::transfer(
                 nft_owner.key,
@@ -139,12 +246,33 @@ impl Processor {
                 system_program.clone()
             ],
         )?;
+        let mut extensions = vec![];
+        if transfer_fee.is_some()
+        {
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        if metadata_address.is_some()
+        {
+            extensions.push(ExtensionType::MetadataPointer);
+        }
+        if transfer_hook.is_some()
+        {
+            extensions.push(ExtensionType::TransferHook);
+        }
+        if !extensions.is_empty() && *token_program_id.key != spl_token_2022::id()
+        {
+            msg!("Mint extensions require the Token-2022 program");
+            return Err(TokenError::InvalidTokenProgram.into());
+        }
+        let mint_len = ExtensionType::try_calculate_account_len::<Mint>(&extensions)?;
+        let mint_rent = rent.minimum_balance(mint_len);
+        assert_withdrawal_keeps_rent(nft_vault, mint_rent, &rent)?;
         invoke_signed(
             &system_instruction::create_account(
                 nft_vault.key,
                 spl_token_mint.key,
-                1461600, //calculate rent 
-                82,//size
+                mint_rent,
+                mint_len as u64,
                 token_program_id.key,
             ),
             &[
@@ -155,9 +283,46 @@ impl Processor {
             &[nft_vault_signer_seeds,spl_token_signer_seeds],
         )?;
 
+        //extensions are initialized before the mint itself, the curator keeps their authorities
+        if let Some((fee_bps, maximum_fee)) = transfer_fee {
+            invoke(
+                &initialize_transfer_fee_config(
+                    token_program_id.key,
+                    spl_token_mint.key,
+                    Some(nft_owner.key),
+                    Some(nft_owner.key),
+                    fee_bps,
+                    maximum_fee,
+                )?,
+                &[token_program_id.clone(), spl_token_mint.clone()],
+            )?;
+        }
+        if let Some(metadata_address) = metadata_address {
+            invoke(
+                &metadata_pointer::instruction::initialize(
+                    token_program_id.key,
+                    spl_token_mint.key,
+                    Some(*nft_owner.key),
+                    Some(metadata_address),
+                )?,
+                &[token_program_id.clone(), spl_token_mint.clone()],
+            )?;
+        }
+        if let Some(transfer_hook) = transfer_hook {
+            //without an authority the hook can't be pointed to a program that blocks the vault transfers
+            invoke(
+                &transfer_hook::instruction::initialize(
+                    token_program_id.key,
+                    spl_token_mint.key,
+                    None,
+                    Some(transfer_hook),
+                )?,
+                &[token_program_id.clone(), spl_token_mint.clone()],
+            )?;
+        }
         msg!("Initialize mint");
         invoke_signed(
-            &spl_token::instruction::initialize_mint(
+            &spl_token_2022::instruction::initialize_mint(
                 token_program_id.key,
                 spl_token_mint.key, 
                 nft_vault.key,
@@ -173,13 +338,41 @@ impl Processor {
                 ],
                 &[nft_vault_signer_seeds,spl_token_signer_seeds]
             )?;
+        if let (Some(transfer_hook_program), Some(extra_account_metas)) = (transfer_hook_program, extra_account_metas) {
+            //the interface has no payer, the curator funds the validation account before the vault signs as mint authority
+            let metas = holder_account_metas()?;
+            transfer_lamports(
+                nft_owner,
+                extra_account_metas,
+                rent.minimum_balance(ExtraAccountMetaList::size_of(metas.len())?),
+                system_program,
+            )?;
+            invoke_signed(
+                &initialize_extra_account_meta_list(
+                    transfer_hook_program.key,
+                    extra_account_metas.key,
+                    spl_token_mint.key,
+                    nft_vault.key,
+                    &metas,
+                ),
+                &[
+                    extra_account_metas.clone(),
+                    spl_token_mint.clone(),
+                    nft_vault.clone(),
+                    system_program.clone(),
+                    transfer_hook_program.clone(),
+                ],
+                &[nft_vault_signer_seeds],
+            )?;
+        }
         // nft owner associated token using spl token mint
         msg!("Create associated token");
         invoke_signed(            
-            &spl_associated_token_account::create_associated_token_account(
+            &spl_associated_token_account::instruction::create_associated_token_account(
                 nft_vault.key,
                 nft_vault.key,////?????
                 spl_token_mint.key,
+                token_program_id.key,
             ),&[
                 nft_vault.clone(),
                 spl_vault_associated_address.clone(),
@@ -193,12 +386,12 @@ impl Processor {
         )?;
         msg!("minting token");
         invoke_signed(
-            &spl_token::instruction::mint_to_checked(
+            &spl_token_2022::instruction::mint_to_checked(
                 token_program_id.key,
                 spl_token_mint.key,
                 spl_vault_associated_address.key,
                 nft_vault.key,
-                &[&nft_vault.key],
+                &[nft_vault.key],
                 number_of_tokens,
                 9
             )?,&[
@@ -214,138 +407,220 @@ impl Processor {
 
         if nft_associated_address.data_is_empty(){
             invoke(            
-                &spl_associated_token_account::create_associated_token_account(
+                &spl_associated_token_account::instruction::create_associated_token_account(
                     nft_owner.key,
                     nft_vault.key,
                     nft_mint.key,
+                    nft_token_program.key,
                 ),&[
                     nft_owner.clone(),
                     nft_associated_address.clone(),
                     nft_vault.clone(),
                     nft_mint.clone(),
-                    token_program_id.clone(),
+                    nft_token_program.clone(),
                     rent_info.clone(),
                     associated_token_info.clone(),
                     system_program.clone()
                 ]
             )?;
-            msg!("Testing");
             msg!("vault {} spl token {} spl vault{}",nft_vault.key,spl_token_mint.key,spl_vault_associated_address.key);
         }
         msg!("transfer");
-        invoke(
-            &spl_token::instruction::transfer(
-                token_program_id.key,
-                nft_owner_nft_associated.key,
-                nft_associated_address.key,
-                nft_owner.key,
-                &[nft_owner.key],
-                1
-            )?,
-            &[
-                token_program_id.clone(),
-                nft_owner_nft_associated.clone(),
-                nft_associated_address.clone(),
-                nft_owner.clone(),
-                system_program.clone()
-            ],
+        transfer_tokens(
+            nft_token_program,
+            nft_owner_nft_associated,
+            nft_mint,
+            nft_associated_address,
+            nft_owner,
+            accounts,
+            1,
+            &[],
         )?;
-        let now = Clock::get()?.unix_timestamp as u64; 
-        let mut escrow = NftDetails::try_from_slice(&pda.data.borrow())?;
-        escrow.number_of_tokens = number_of_tokens;
-        escrow.price = price;
-        escrow.nft_mint = *nft_mint.key;
-        escrow.nft_escrow = nft_vault_address;
-        escrow.nft_owner=*nft_owner.key;
-        escrow.token_mint = *spl_token_mint.key;
-        escrow.create_at = now;
-        escrow.days = 0 as f64;
-        escrow.remaining_token=number_of_tokens;
-        escrow.serialize(&mut &mut pda.data.borrow_mut()[..])?;
+        let now = current_timestamp()?; 
+        let escrow = NftDetails{
+            account_type: AccountType::NftDetails,
+            version: NftDetails::VERSION,
+            number_of_tokens,
+            price,
+            nft_mint: *nft_mint.key,
+            nft_escrow: *nft_vault.key,
+            nft_owner: *nft_owner.key,
+            token_mint: *spl_token_mint.key,
+            create_at: now,
+            remaining_token: number_of_tokens,
+            current_day: 0,
+            last_settled_day: 0,
+            total_auctions: 0,
+            protocol_fees: 0,
+            royalties_paid: 0,
+            split_proceeds: !beneficiaries.is_empty(),
+            reserve_price: 0,
+            auction_interval: DEFAULT_AUCTION_INTERVAL,
+            inflation_bps: DEFAULT_INFLATION_BPS,
+            period_start: now,
+            period_offset: 0,
+            last_auction_price: 0,
+            last_auction_tokens: 0,
+            bought_out: false,
+            twap_price: 0,
+            twap_updated_at: 0,
+            valuation: 0,
+            quote_mint,
+            token_program: *token_program_id.key,
+            nft_token_program: *nft_token_program.key,
+            min_holding_period,
+            allowlist_root,
+            max_per_wallet,
+            sale_mode,
+            start_price,
+            sale_refunds: sale_mode == SaleMode::Dutch && sale_refunds,
+            clearing_price: 0,
+            curve_param,
+            sale_reserve: 0,
+            min_raise,
+            raised: 0,
+            burn_unsold,
+            open_auctions: 0,
+            pending_flips: 0,
+            quote_token_program: *quote_token_program.key,
+        };
+        escrow.save(pda)?;
+        if !beneficiaries.is_empty()
+        {
+            let proceeds_info = proceeds_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let proceeds = build_proceeds(pda.key, &beneficiaries)?;
+            let proceeds_signer_seeds: &[&[_]] = &[
+                PROCEEDSPREFIX.as_bytes(),
+                &nft_owner.key.to_bytes(),
+                &pda.key.to_bytes(),
+                &[proceeds_bump],
+            ];
+            create_account_signed(
+                nft_owner,
+                rent.minimum_balance(Proceeds::LEN),
+                Proceeds::LEN,
+                program_id,
+                system_program,
+                proceeds_info,
+                proceeds_signer_seeds,
+            )?;
+            assert_rent_exempt(proceeds_info, &rent)?;
+            proceeds.save(proceeds_info)?;
+            msg!("Proceeds split between {} beneficiaries",proceeds.count);
+        }
+        if quote_mint != Pubkey::default()
+        {
+            // quote accounts of the vault and the proceeds pda, bids and sales are escrowed in the quote mint
+            let quote_mint_info = quote_mint_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut quote_accounts = vec![(vault_quote.ok_or(ProgramError::NotEnoughAccountKeys)?, nft_vault)];
+            if let Some(proceeds_info) = proceeds_info.filter(|_| !beneficiaries.is_empty()) {
+                quote_accounts.push((proceeds_quote.ok_or(ProgramError::NotEnoughAccountKeys)?, proceeds_info));
+            }
+            for (quote_account, wallet) in quote_accounts {
+                if !quote_account.data_is_empty()
+                {
+                    continue;
+                }
+                invoke(
+                    &spl_associated_token_account::instruction::create_associated_token_account(
+                        nft_owner.key,
+                        wallet.key,
+                        quote_mint_info.key,
+                        quote_token_program.key,
+                    ),&[
+                        nft_owner.clone(),
+                        quote_account.clone(),
+                        wallet.clone(),
+                        quote_mint_info.clone(),
+                        quote_token_program.clone(),
+                        rent_info.clone(),
+                        associated_token_info.clone(),
+                        system_program.clone()
+                    ]
+                )?;
+            }
+            msg!("Vault priced in {}",quote_mint);
+        }
         
         Ok(())
     }
     pub fn auction1(program_id: &Pubkey,accounts: &[AccountInfo], price:u64)->ProgramResult{   
         //Program to auction
-        let account_info_iter = &mut accounts.iter();
-        let bidder =  next_account_info(account_info_iter)?; // sender or signer
-        let nft_owner = next_account_info(account_info_iter)?; // auction creator
-        let pda_data = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
-        let nft_vault = next_account_info(account_info_iter)?; // nft vault which saves the amount 
-        let auction_data = next_account_info(account_info_iter)?; //account made using Auction Prefix, Nft owner and Day
-        let system_program = next_account_info(account_info_iter)?;//system_program
-        let rent_info  = next_account_info(account_info_iter)?; // rent 
-
-        if !bidder.is_signer
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        if pda_data.owner!=program_id  
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        let (nft_vault_address, bump_seed) = generate_pda_and_bump_seed(
-            NFTPREFIX,
-            nft_owner.key,
-            pda_data.key,
-            program_id
-        );
+        let now = current_timestamp()?; 
+        let AuctionAccounts{
+            bidder,
+            nft_owner,
+            pda_data,
+            nft_vault,
+            auction_data,
+            system_program,
+            rent_info,
+            bidder_quote,
+            vault_quote,
+            max_payer,
+            max_payer_quote,
+            quote,
+            escrow: mut pda_check,
+            auction,
+            day,
+            nft_vault_bump,
+            auction_bump,
+        } = AuctionAccounts::validate(program_id, accounts, now)?;
 
-        //check if the nft_vault is actual pda or not 
-        if nft_vault_address != *nft_vault.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);   
-        }
         let nft_vault_signer_seeds: &[&[_]] = &[
             NFTPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda_data.key.to_bytes(),
-            &[bump_seed],
+            &[nft_vault_bump],
         ];
-        let  mut pda_check = NftDetails::try_from_slice(&pda_data.data.borrow())?;
-        let num_of_token=pda_check.number_of_tokens/100;
-        if *nft_vault.key != pda_check.nft_escrow
+        let num_of_token=pda_check.auction_tokens();
+
+        if pda_check.bought_out
         {
-            return Err(ProgramError::MissingRequiredSignature);   
+            return Err(TokenError::VaultBoughtOut.into());
         }
-        let now = Clock::get()?.unix_timestamp as u64; 
-
-        if (now-pda_check.create_at) < 86400
+        if pda_check.raise_failed(now)?
+        {
+            return Err(TokenError::RaiseFailed.into());
+        }
+        if day == 0
         {
             
             msg!("The auction period has not started yet");
             return Err(TokenError::Notstarted.into());
 
         }
-        let day:u64=1;
+        //a zero bid could never be settled and would keep the auction open forever
+        if price == 0
+        {
+            return Err(TokenError::PriceLower.into());
+        }
+        if price < pda_check.reserve_price
+        {
+            msg!("The bid is below the reserve price {}",pda_check.reserve_price);
+            return Err(TokenError::PriceLower.into());
+        }
         let day_ip=day.to_string();
-        let (auction_address,auction_bump)= Pubkey::find_program_address(
-            &[
-                AUCTIONPREFIX.as_bytes(),
-                &nft_owner.key.to_bytes(),
-                day_ip.as_bytes(),
-            ],
-            program_id,
-        );
-        
         let auction_signer_seeds: &[&[_]] = &[
             AUCTIONPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
+            &pda_data.key.to_bytes(),
             day_ip.as_bytes(),
             &[auction_bump],
         ];
         msg!("The day is: {}",day);
-        if *auction_data.key!=auction_address 
+        let mut flag:u8=0;
+        let mut auction_operation;
+        if let Some(auction) = auction
         {
-            msg!("auction address don't match {}",auction_address);
-            return Err(ProgramError::MissingRequiredSignature);  
+            msg!("Account already created so owner is checked");
+            auction_operation = auction;
         }
-        let mut flag:u8=0;
-        if auction_data.data_is_empty()
+        else
         {
             msg!("Inside account creation");
             let rent = Rent::get()?;
-            let transfer_amount =  rent.minimum_balance(std::mem::size_of::<Auction>());
+            let transfer_amount =  rent.minimum_balance(Auction::LEN);
             invoke(
                 &system_instruction::transfer(
                     bidder.key,
@@ -359,12 +634,13 @@ impl Processor {
                 ],
             )?;
             msg!("Account Creation fees sent");
+            assert_withdrawal_keeps_rent(nft_vault, transfer_amount, &rent)?;
             invoke_signed(
                 &system_instruction::create_account(
                     nft_vault.key,
                     auction_data.key,
                     transfer_amount, 
-                    std::mem::size_of::<Auction>() as u64,
+                    Auction::LEN as u64,
                     program_id,
                 ),
                 &[
@@ -375,37 +651,35 @@ impl Processor {
                 ],
                 &[nft_vault_signer_seeds,auction_signer_seeds],
             )?;
+            assert_rent_exempt(auction_data, &rent)?;
             msg!("Account created");
-            let total_supply=pda_check.number_of_tokens/100+pda_check.number_of_tokens;
+            let total_supply=pda_check.number_of_tokens
+                .checked_add(num_of_token)
+                .ok_or(TokenError::Overflow)?;
             pda_check.number_of_tokens=total_supply;
+            pda_check.current_day=day;
+            pda_check.total_auctions=pda_check.total_auctions
+                .checked_add(1)
+                .ok_or(TokenError::Overflow)?;
+            pda_check.open_auctions=pda_check.open_auctions
+                .checked_add(1)
+                .ok_or(TokenError::Overflow)?;
             flag =1;
-        }
-        else
-        {
-            msg!("Account already created so owner is checked");
-            if auction_data.owner!=program_id
-            {
-                return Err(ProgramError::MissingRequiredSignature);   
-            }
+            auction_operation = Auction{
+                account_type: AccountType::Auction,
+                version: Auction::VERSION,
+                vault: *pda_data.key,
+                max_price: 0,
+                max_payer: Pubkey::default(),
+                num_tokens: 0,
+                day,
+            };
         }
 
-       
-        let mut auction_operation = Auction::try_from_slice(&auction_data.data.borrow())?;
         if flag ==1
         {
             msg!("Bid amount transfer for first time...");
-            invoke(
-                &system_instruction::transfer(
-                    bidder.key,
-                    nft_vault.key,
-                    price,
-                ),
-                &[
-                    bidder.clone(),
-                    nft_vault.clone(),
-                    system_program.clone()
-                ],
-            )?;
+            quote.transfer(bidder_quote, bidder, vault_quote, price, None)?;
             msg!("Transfer completed");
             auction_operation.max_payer = *bidder.key;
             auction_operation.num_tokens = num_of_token;
@@ -419,94 +693,74 @@ impl Processor {
             if price > auction_operation.max_price 
             {
 
-            let max_payer=next_account_info(account_info_iter)?; // previous maximum payer obtained by deserializing auction_data
-            if *max_payer.key!=auction_operation.max_payer
-            {
-                return Err(ProgramError::MissingRequiredSignature);   
-            }
+            let max_payer=max_payer.ok_or(ProgramError::NotEnoughAccountKeys)?; // previous maximum payer obtained by deserializing auction_data
+            let max_payer_quote = if quote.is_native() {
+                max_payer
+            } else {
+                max_payer_quote.ok_or(ProgramError::NotEnoughAccountKeys)?
+            };
             msg!("release amount of previous highest bidder");
             //release amount of previous highest bidder
-            invoke_signed(  
-                &system_instruction::transfer(
-                nft_vault.key,
-                max_payer.key,
-                auction_operation.max_price,    
-            ),
-            &[
-            nft_vault.clone(),
-            max_payer.clone(),
-            system_program.clone(),
-            ],
-            &[&nft_vault_signer_seeds],
-            )?;
+            quote.transfer(vault_quote, nft_vault, max_payer_quote, auction_operation.max_price, Some(nft_vault_signer_seeds))?;
             msg!("amount released");
 
             auction_operation.max_payer = *bidder.key;
             auction_operation.num_tokens = num_of_token;
             auction_operation.max_price=price;
             msg!("bid amount to vault ..");
-            invoke(
-                &system_instruction::transfer(
-                    bidder.key,
-                    nft_vault.key,
-                    price,
-                ),
-                &[
-                    bidder.clone(),
-                    nft_vault.clone(),
-                    system_program.clone()
-                ],
-            )?;
+            quote.transfer(bidder_quote, bidder, vault_quote, price, None)?;
             msg!("completed");
         }
 
         }
-        auction_operation.serialize(&mut &mut auction_data.data.borrow_mut()[..])?;
-        pda_check.serialize(&mut &mut pda_data.data.borrow_mut()[..])?;
+        auction_operation.save(auction_data)?;
+        pda_check.save(pda_data)?;
 
        Ok(())
 
     }
-    pub fn process_buy_nft_token(program_id: &Pubkey,accounts: &[AccountInfo],token:u64,price:u64)-> ProgramResult {
+    pub fn process_buy_nft_token(program_id: &Pubkey,accounts: &[AccountInfo],token:u64,price:u64,allowance: Option<(u64, Vec<[u8; 32]>)>)-> ProgramResult {
         //program to buy nft at the price set by the program initiator
-        let account_info_iter = &mut accounts.iter();
-        let buyer =  next_account_info(account_info_iter)?; // sender or signer
-        let nft_owner = next_account_info(account_info_iter)?; // auction creator
-        let pda_data = next_account_info(account_info_iter)?; // pda data that consists number of tokens 
-        let token_program_id = next_account_info(account_info_iter)?; //TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA
-        let nft_vault = next_account_info(account_info_iter)?; // nft vault
-        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
-        let buyer_spl_associated =  next_account_info(account_info_iter)?; // sender or signer
-        let spl_token_mint = next_account_info(account_info_iter)?; 
-        let rent_info = next_account_info(account_info_iter)?; 
-        let associated_token_info = next_account_info(account_info_iter)?; 
-        let system_program = next_account_info(account_info_iter)?;
-
-        //verifying pda_data
-        if pda_data.owner!=program_id
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        if !buyer.is_signer
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        let BuyAccounts{
+            buyer,
+            nft_owner,
+            pda_data,
+            token_program_id,
+            nft_vault,
+            spl_vault_associated_address,
+            buyer_spl_associated,
+            spl_token_mint,
+            rent_info,
+            associated_token_info,
+            system_program,
+            config_info,
+            treasury,
+            proceeds_info,
+            buyer_quote,
+            owner_quote,
+            proceeds_quote,
+            vault_quote,
+            purchase_info,
+            creators,
+            mut escrow,
+            mut config,
+            mut proceeds,
+            royalties,
+            quote,
+            purchase,
+            nft_vault_bump,
+            purchase_bump,
+            ..
+        } = BuyAccounts::validate(program_id, accounts)?;
 
-        let mut escrow = NftDetails::try_from_slice(&pda_data.data.borrow())?;
-        //verifying owner and escrow
-        if escrow.nft_escrow!=*nft_vault.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        if escrow.nft_owner!=*nft_owner.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
         msg!("spl: {}", spl_token_mint.key);
         msg!("token: {}", token);
-        let now = Clock::get()?.unix_timestamp as u64; 
-        let passed_time = now - escrow.create_at;
-        if passed_time >= 86400 {
+        let now = current_timestamp()?; 
+        if escrow.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        if escrow.day_at(now)? > 0 {
             msg!("The buying period has ended you can only auction now");
             return Err(TokenError::AuctionStarted.into());
         }
@@ -515,63 +769,63 @@ impl Processor {
             msg!("The remaining token is only {}",escrow.remaining_token);
             return Err(TokenError::TokenFinished.into());
         }
-        if price < escrow.price
+        let sale_price = escrow.sale_price_at(now)?;
+        let amount = escrow.sale_cost(now, token)?;
+        if amount as u128 > token as u128 * price as u128
         {
-            msg!("The price is lower then set");
+            msg!("The price is lower then {}",amount / token.max(1));
             return Err(TokenError::PriceLower.into());
         }
-        let (nft_vault_address, bump_seed) = generate_pda_and_bump_seed(
-            NFTPREFIX,
-            nft_owner.key,
-            pda_data.key,
-            program_id
-        );
-        if nft_vault_address != *nft_vault.key
+        //a refundable dutch sale settles the floor price now and escrows the premium until the clearing price is known,
+        //a bonding curve or a crowdfund escrows the whole payment as the sale reserve
+        let premium = if escrow.sale_refunds {
+            amount - token * escrow.price
+        } else if escrow.escrows_sale() {
+            amount
+        } else {
+            0
+        };
+        let mut allowlist_cap = None;
+        if escrow.allowlist_root != [0; 32]
         {
-            return Err(ProgramError::MissingRequiredSignature);   
+            let (cap, proof) = allowance.ok_or(TokenError::NotAllowlisted)?;
+            if !verify_merkle_proof(&proof, &escrow.allowlist_root, allowlist_leaf(buyer.key, cap))
+            {
+                msg!("{} with a cap of {} is not in the allowlist",buyer.key,cap);
+                return Err(TokenError::NotAllowlisted.into());
+            }
+            allowlist_cap = Some(cap);
+        }
+        //capped, refundable and crowdfunded sales track what every wallet bought in its purchase pda
+        if allowlist_cap.is_some() || escrow.max_per_wallet > 0 || escrow.sale_refunds || escrow.min_raise > 0
+        {
+            let mut purchase = open_purchase(program_id, buyer, pda_data, purchase_info, system_program, purchase, purchase_bump)?;
+            purchase.add_bought(token, allowlist_cap, escrow.max_per_wallet)?;
+            if escrow.sale_refunds
+            {
+                purchase.premium = purchase.premium.checked_add(premium).ok_or(TokenError::Overflow)?;
+            }
+            if escrow.min_raise > 0
+            {
+                purchase.paid = purchase.paid.checked_add(amount).ok_or(TokenError::Overflow)?;
+            }
+            purchase.save(purchase_info)?;
         }
-
         let nft_vault_signer_seeds: &[&[_]] = &[
             NFTPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda_data.key.to_bytes(),
-            &[bump_seed],
+            &[nft_vault_bump],
         ];
 
-         // new token mint for minting
-         let (spl_token_address, _bump_seed_spl) = generate_pda_and_bump_seed(
-            SPLTOKENPREFIX,
-            nft_owner.key,
-            pda_data.key,
-            program_id
-        );
-         // verifying mint
-        if spl_token_address!=*spl_token_mint.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        //verifying spl mint token account
-        let spl_vault_associated_token =get_associated_token_address(nft_vault.key,spl_token_mint.key);
-
-        if spl_vault_associated_token!=*spl_vault_associated_address.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-
-        //verifying buyer token account
-        let buyer_associated_address =get_associated_token_address(buyer.key,spl_token_mint.key);
-        if buyer_associated_address!=*buyer_spl_associated.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-
         if buyer_spl_associated.data_is_empty()
         {
         invoke(            
-            &spl_associated_token_account::create_associated_token_account(
+            &spl_associated_token_account::instruction::create_associated_token_account(
                 buyer.key,
                 buyer.key,
                 spl_token_mint.key,
+                token_program_id.key,
             ),&[
                 buyer.clone(),
                 buyer_spl_associated.clone(),
@@ -584,154 +838,103 @@ impl Processor {
             ]
         )?;
         }
-        invoke(  
-            &system_instruction::transfer(
-            buyer.key,
-            nft_owner.key,
-            token*escrow.price,    
-        ),
-            &[
-            nft_vault.clone(),
-            nft_owner.clone(),
-            buyer.clone(),
-            system_program.clone()
-            ],
-            )?;
-        invoke_signed(
-            &spl_token::instruction::transfer(
-                token_program_id.key,
-                spl_vault_associated_address.key,
-                buyer_spl_associated.key,
-                nft_vault.key,
-                &[nft_vault.key],
-                token,
-            )?,
-            &[
-                token_program_id.clone(),
-                spl_vault_associated_address.clone(),
-                buyer_spl_associated.clone(),
-                nft_vault.clone(),
-                system_program.clone()
-            ],&[&nft_vault_signer_seeds],
+        let settled = amount - premium;
+        let fee = config.fee_for(settled)?;
+        msg!("Protocol fee {}",fee);
+        quote.transfer(buyer_quote, buyer, treasury, fee, None)?;
+        let royalty = pay_royalties(&quote, buyer_quote, buyer, creators, None, &royalties, settled - fee)?;
+        let payee = if proceeds.is_some() { proceeds_quote } else { owner_quote };
+        pay_curator(&quote, buyer_quote, buyer, payee, &mut proceeds, None, settled - fee - royalty)?;
+        quote.transfer(buyer_quote, buyer, vault_quote, premium, None)?;
+        transfer_tokens(
+            token_program_id,
+            spl_vault_associated_address,
+            spl_token_mint,
+            buyer_spl_associated,
+            nft_vault,
+            accounts,
+            token,
+            &[nft_vault_signer_seeds],
         )?;
 
        
-        escrow.remaining_token-=token;
-        escrow.serialize(&mut &mut pda_data.data.borrow_mut()[..])?;
+        escrow.remaining_token=escrow.remaining_token
+            .checked_sub(token)
+            .ok_or(TokenError::Overflow)?;
+        if escrow.sale_mode == SaleMode::Dutch
+        {
+            escrow.clearing_price = sale_price;
+        }
+        if escrow.escrows_sale()
+        {
+            escrow.sale_reserve = escrow.sale_reserve.checked_add(amount).ok_or(TokenError::Overflow)?;
+            escrow.raised = escrow.raised.checked_add(amount).ok_or(TokenError::Overflow)?;
+        }
+        record_protocol_fee(&mut escrow, &mut config, fee)?;
+        record_royalty(&mut escrow, royalty)?;
+        escrow.save(pda_data)?;
+        config.save(config_info)?;
+        if let Some(proceeds) = proceeds {
+            proceeds.save(proceeds_info)?;
+        }
         Ok(())
     }
     pub fn process_buy_nft_token2(program_id: &Pubkey,accounts: &[AccountInfo],day:u64)-> ProgramResult {
         //The winner of the auction can claim the tokens
-        let account_info_iter = &mut accounts.iter();
-        let buyer =  next_account_info(account_info_iter)?; // sender or signer
-        let nft_owner = next_account_info(account_info_iter)?; // auction creator
-        let pda_data = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
-        let token_program_id = next_account_info(account_info_iter)?; //TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA
-        let nft_vault = next_account_info(account_info_iter)?; // nft vault
-        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
-        let buyer_spl_associated =  next_account_info(account_info_iter)?; // sender or signer
-        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
-        let system_program = next_account_info(account_info_iter)?; 
-        let rent_info =next_account_info(account_info_iter)?; 
-        let auction_data=next_account_info(account_info_iter)?;
-        let associated_token_info= next_account_info(account_info_iter)?;
-
-         //verifying pda_data
-         if pda_data.owner!=program_id
-         {
-             return Err(ProgramError::MissingRequiredSignature);
-         }
-         if !buyer.is_signer
-         {
-             return Err(ProgramError::MissingRequiredSignature);
-         }
- 
-        let escrow = NftDetails::try_from_slice(&pda_data.data.borrow())?;
-        if escrow.nft_escrow!=*nft_vault.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        if escrow.nft_owner!=*nft_owner.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        let now = Clock::get()?.unix_timestamp as u64; 
-        let days = (now - escrow.create_at)/86400;
-        let (nft_vault_address, bump_seed) = generate_pda_and_bump_seed(
-            NFTPREFIX,
-            nft_owner.key,
-            pda_data.key,
-            program_id
-        );
-          //check if the nft_vault is actual pda or not 
-          if nft_vault_address != *nft_vault.key
-          {
-              return Err(ProgramError::MissingRequiredSignature);   
-          }
+        let SettleAccounts{
+            buyer,
+            nft_owner,
+            pda_data,
+            token_program_id,
+            nft_vault,
+            spl_vault_associated_address,
+            buyer_spl_associated,
+            spl_token_mint,
+            system_program,
+            rent_info,
+            auction_data,
+            associated_token_info,
+            config_info,
+            treasury,
+            proceeds_info,
+            stake_pool_info,
+            vault_quote,
+            owner_quote,
+            proceeds_quote,
+            stake_pool_quote,
+            creators,
+            mut escrow,
+            auction: mut auction_operation,
+            mut config,
+            mut proceeds,
+            mut stake_pool,
+            royalties,
+            quote,
+            nft_vault_bump,
+            spl_token_bump,
+            ..
+        } = SettleAccounts::validate(program_id, accounts, day)?;
+
+        //auctions opened before a buyout still settle, their fractions were counted as redeemable
+        let now = current_timestamp()?; 
+        let days = escrow.day_at(now)?;
         let nft_vault_signer_seeds: &[&[_]] = &[
             NFTPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda_data.key.to_bytes(),
-            &[bump_seed],
+            &[nft_vault_bump],
         ];
-        let (spl_token_address, bump_seed_spl) = generate_pda_and_bump_seed(
-            SPLTOKENPREFIX,
-            nft_owner.key,
-            pda_data.key,
-            program_id
-        );
-        // verifying mint
-        if spl_token_address!=*spl_token_mint.key
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
         let spl_token_signer_seeds: &[&[_]] = &[
             SPLTOKENPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda_data.key.to_bytes(),
-            &[bump_seed_spl],
+            &[spl_token_bump],
         ];
-         //verifying spl mint token account
-         let spl_vault_associated_token =get_associated_token_address(nft_vault.key,spl_token_mint.key);
-
-         if spl_vault_associated_token!=*spl_vault_associated_address.key
-         {
-             return Err(ProgramError::MissingRequiredSignature);
-         }
- 
-         //verifying buyer token account
-         let buyer_associated_address =get_associated_token_address(buyer.key,spl_token_mint.key);
-         if buyer_associated_address!=*buyer_spl_associated.key
-         {
-             return Err(ProgramError::MissingRequiredSignature);
-         }
-
-        let day_ip=day.to_string();
-        let (auction_address,_auction_bump)= Pubkey::find_program_address(
-            &[
-                AUCTIONPREFIX.as_bytes(),
-                &nft_owner.key.to_bytes(),
-                day_ip.as_bytes(),
-            ],
-            program_id,
-        );
-        if auction_data.data_is_empty()
-        {
-            msg!("The auction data is empty");
-            return Err(TokenError::Notstarted.into());
-        }
-        if auction_address!=*auction_data.key && auction_data.owner !=program_id
-        {
-            return Err(ProgramError::MissingRequiredSignature);   
-        }
-              
-        let mut auction_operation = Auction::try_from_slice(&auction_data.data.borrow())?;
-        
 
-        if day!=auction_operation.day && day>days
+        if day!=auction_operation.day || day>=days
         {
-            msg!("The day after auction doesn't match");
-            return Err(TokenError::Notstarted.into());
+            msg!("The auction of day {} is still running or doesn't match",day);
+            return Err(TokenError::WrongAuctionDay.into());
 
         }
         if auction_operation.max_payer==*buyer.key&& auction_operation.max_price!=0{
@@ -739,10 +942,11 @@ impl Processor {
             if buyer_spl_associated.data_is_empty()
             {
                 invoke(            
-                    &spl_associated_token_account::create_associated_token_account(
+                    &spl_associated_token_account::instruction::create_associated_token_account(
                         buyer.key,
                         buyer.key,
                         spl_token_mint.key,
+                        token_program_id.key,
                     ),&[
                         buyer.clone(),
                         buyer_spl_associated.clone(),
@@ -759,12 +963,12 @@ impl Processor {
             }
             msg!("Token account created or already exist");
                 invoke_signed(
-                &spl_token::instruction::mint_to_checked(
+                &spl_token_2022::instruction::mint_to_checked(
                     token_program_id.key,
                     spl_token_mint.key,
                     spl_vault_associated_address.key,
                     nft_vault.key,
-                    &[&nft_vault.key],
+                    &[nft_vault.key],
                     auction_operation.num_tokens,
                     9
                 )?,&[
@@ -779,321 +983,1795 @@ impl Processor {
             )?;
             msg!("Token minted to nft_vault");
 
-        invoke_signed(
-            &spl_token::instruction::transfer(
-                token_program_id.key,
-                spl_vault_associated_address.key,
-                buyer_spl_associated.key,
-                nft_vault.key,
-                &[nft_vault.key],
-                auction_operation.num_tokens,
-            )?,
-            &[
-                token_program_id.clone(),
-                spl_vault_associated_address.clone(),
-                buyer_spl_associated.clone(),
-                nft_vault.clone(),
-                system_program.clone()
-            ],&[&nft_vault_signer_seeds],
+        transfer_tokens(
+            token_program_id,
+            spl_vault_associated_address,
+            spl_token_mint,
+            buyer_spl_associated,
+            nft_vault,
+            accounts,
+            auction_operation.num_tokens,
+            &[nft_vault_signer_seeds],
         )?;
         msg!("Token transfered to winner");
-        invoke_signed(  
-            &system_instruction::transfer(
-            nft_vault.key,
-            nft_owner.key,
-            auction_operation.max_price,    
-        ),
-            &[
-            nft_vault.clone(),
-            nft_owner.clone(),
-            system_program.clone(),
-            ],&[&nft_vault_signer_seeds],
-            )?;
+        let fee = config.fee_for(auction_operation.max_price)?;
+        msg!("Protocol fee {}",fee);
+        quote.transfer(vault_quote, nft_vault, treasury, fee, Some(nft_vault_signer_seeds))?;
+        let royalty = pay_royalties(&quote, vault_quote, nft_vault, creators, Some(nft_vault_signer_seeds), &royalties, auction_operation.max_price - fee)?;
+        let auction_proceeds = auction_operation.max_price - fee - royalty;
+        match &mut stake_pool {
+            Some(stake_pool) if stake_pool.total_staked > 0 => {
+                //auction revenue flows to the fraction holders staking in the vault
+                quote.transfer(vault_quote, nft_vault, stake_pool_quote, auction_proceeds, Some(nft_vault_signer_seeds))?;
+                stake_pool.distribute(auction_proceeds)?;
+                msg!("Auction proceeds distributed to {} staked tokens",stake_pool.total_staked);
+            }
+            _ => {
+                let payee = if proceeds.is_some() { proceeds_quote } else { owner_quote };
+                pay_curator(&quote, vault_quote, nft_vault, payee, &mut proceeds, Some(nft_vault_signer_seeds), auction_proceeds)?
+            }
+        }
+        record_protocol_fee(&mut escrow, &mut config, fee)?;
+        record_royalty(&mut escrow, royalty)?;
         msg!("Amount Released to nft owner");
+        escrow.record_clearing_price(now, auction_operation.max_price, auction_operation.num_tokens)?;
+        msg!("Implied valuation {}",escrow.valuation);
         auction_operation.max_price=0;
+        if day > escrow.last_settled_day
+        {
+            escrow.last_settled_day=day;
+        }
+        escrow.open_auctions = escrow.open_auctions.saturating_sub(1);
+        }
+        else if auction_operation.max_payer==*buyer.key
+        {
+            msg!("You have already claimed");
+            return Err(TokenError::AlreadyClaimed.into());
         }
         else
         {
-            msg!("You have already claimed or not the winner");
+            msg!("You are not the winner");
+            return Err(TokenError::NotAuctionWinner.into());
+        }
+        auction_operation.save(auction_data)?;
+        escrow.save(pda_data)?;
+        config.save(config_info)?;
+        if let Some(proceeds) = proceeds {
+            proceeds.save(proceeds_info)?;
+        }
+        if let Some(stake_pool) = stake_pool {
+            stake_pool.save(stake_pool_info)?;
         }
-        auction_operation.serialize(&mut &mut auction_data.data.borrow_mut()[..])?;
-        escrow.serialize(&mut &mut pda_data.data.borrow_mut()[..])?;
 
         Ok(())
     }
     // need to improve security using recent blockhash or vrf
     pub fn process_coin_flip(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let player =  next_account_info(account_info_iter)?; // sender or signer
-        let coinflip_pda = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
-        let token_program_id = next_account_info(account_info_iter)?; //TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA
-        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
-        let player_associated_token = next_account_info(account_info_iter)?; // spl token mint associate account
-        let spl_token_mint = next_account_info(account_info_iter)?; // spl token mint
-        let nft_owner = next_account_info(account_info_iter)?; // auction creator
-        let system_program = next_account_info(account_info_iter)?; 
-        let pda =next_account_info(account_info_iter)?;  // main data account
-        let nft_vault = next_account_info(account_info_iter)?; // nft vault
-
-        if !player.is_signer
-        {
-            msg!("The player is not the signer");
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        let now = Clock::get()?.unix_timestamp as u64; 
-        let rent = Rent::get()?;
-        let transfer_amount =  rent.minimum_balance(std::mem::size_of::<CoinFlip>());
-        let (spl_token_address, _bump_seed_spl) = generate_pda_and_bump_seed(
-            SPLTOKENPREFIX,
-            nft_owner.key,
-            pda.key,
-            program_id
-        );
-        // verifying mint
-        if spl_token_address!=*spl_token_mint.key
-        {
-            msg!("SPL Token mint doesn't matches");
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        let (nft_vault_address, _bump_seed) = generate_pda_and_bump_seed(
-            NFTPREFIX,
-            nft_owner.key,
-            pda.key,
-            program_id
-        );
-          //check if the nft_vault is actual pda or not 
-          if nft_vault_address != *nft_vault.key
-          {
-            msg!("NFT vault doesn't match");
-              return Err(ProgramError::MissingRequiredSignature);   
-          }
-        if pda.owner!=program_id
-        {
-            return Err(ProgramError::MissingRequiredSignature);   
-        }
-        let  pda_check = NftDetails::try_from_slice(&pda.data.borrow())?;
-        let token_balance=get_token_balance(player_associated_token)?;
-        //verifying mint token account
-        let player_token_address= get_associated_token_address(player.key, spl_token_mint.key);
-        if player_associated_token.data_is_empty() && player_token_address!=*player_associated_token.key
+        let CoinFlipAccounts{
+            player,
+            coinflip_pda,
+            token_program_id,
+            spl_vault_associated_address,
+            player_associated_token,
+            spl_token_mint,
+            system_program,
+            pda,
+            escrow: mut pda_check,
+            holding,
+            ..
+        } = CoinFlipAccounts::validate(program_id, accounts)?;
+
+        if pda_check.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        let now = current_timestamp()?; 
+        let rent = Rent::get()?;
+        let transfer_amount =  rent.minimum_balance(CoinFlip::LEN);
+        //a player who staked all their fractions may have no token account left, only the stake counts then
+        let has_token_account = !player_associated_token.data_is_empty();
+        if !has_token_account && !matches!(holding, Some(HoldingProof::Stake(_)))
         {
             msg!("You don't have token at all");
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(TokenError::InsufficientStake.into());
+        }
+        let token_balance = if has_token_account { get_token_balance(player_associated_token)? } else { 0 };
+        //2/3 of the supply computed in u128 so large supplies can't overflow
+        let required_balance = (pda_check.number_of_tokens as u128 * 2 / 3) as u64;
+        //with a holding period only the fractions held throughout the period count
+        let held_balance = match &holding {
+            None => token_balance,
+            Some(HoldingProof::Holder(record)) => {
+                let since = now.saturating_sub(pda_check.min_holding_period);
+                record.held_balance_since(since).min(token_balance)
+            }
+            Some(HoldingProof::Stake(stake_entry)) => {
+                if seconds_since(now, stake_entry.staked_at)? >= pda_check.min_holding_period {
+                    stake_entry.amount
+                } else {
+                    0
+                }
+            }
+        };
+        if held_balance < required_balance
+        {
+            if holding.is_some()
+            {
+                msg!("The fractions have to be held for {} seconds",pda_check.min_holding_period);
+                return Err(TokenError::HoldingPeriodNotMet.into());
+            }
+            msg!("You don't have enough tokens");
+            return Err(TokenError::InsufficientStake.into());
+        }
+       //creating coinflip account
+        create_pda_account( 
+            player,
+            transfer_amount,
+            CoinFlip::LEN,
+            program_id,
+            system_program,
+            coinflip_pda
+        )?;
+        assert_rent_exempt(coinflip_pda, &rent)?;
+
+        let mut coinflip = CoinFlip{
+            account_type: AccountType::CoinFlip,
+            version: CoinFlip::VERSION,
+            won: 0,
+            address: Pubkey::default(),
+            amount: 0,
+        };
+        if has_token_account
+        {
+            msg!("Transfering token ....");
+            transfer_tokens(
+                token_program_id,
+                player_associated_token,
+                spl_token_mint,
+                spl_vault_associated_address,
+                player,
+                accounts,
+                token_balance/1000,
+                &[],
+            )?;
+        }
+
+        msg!("Flipping the Coin");
+        if now % 2 == 0 {
+            msg!("You have lost");
+            coinflip.won = 0
+        }
+        else 
+        {
+            msg!("You have won, claim the reward");
+            coinflip.won = 1;
+            coinflip.address=*player.key;
+            coinflip.amount=pda_check.number_of_tokens/100; //1% of total tokens
+            pda_check.pending_flips = pda_check.pending_flips
+                .checked_add(1)
+                .ok_or(TokenError::Overflow)?;
+            pda_check.save(pda)?;
+        }
+        coinflip.save(coinflip_pda)?;
+        Ok(())
+    }
+    pub fn process_coin_flip_claim(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        msg!("Inside the function");
+        let ClaimCoinFlipAccounts{
+            player,
+            nft_owner,
+            pda,
+            coinflip_pda,
+            token_program_id,
+            nft_vault,
+            spl_vault_associated_address,
+            buyer_spl_associated,
+            spl_token_mint,
+            system_program,
+            rent_info,
+            config_info,
+            treasury,
+            proceeds_info,
+            player_quote,
+            owner_quote,
+            proceeds_quote,
+            creators,
+            escrow: mut pda_check,
+            mut coinflip,
+            mut config,
+            mut proceeds,
+            royalties,
+            quote,
+            nft_vault_bump,
+            spl_token_bump,
+            ..
+        } = ClaimCoinFlipAccounts::validate(program_id, accounts)?;
+
+        if pda_check.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[nft_vault_bump],
+        ];
+        let spl_token_signer_seeds: &[&[_]] = &[
+            SPLTOKENPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[spl_token_bump],
+        ];
+        if coinflip.won == 1 && coinflip.address == *player.key
+        {
+            msg!("The winner is verified");
+            msg!("Minting tokens");
+            invoke_signed(
+                &spl_token_2022::instruction::mint_to_checked(
+                    token_program_id.key,
+                    spl_token_mint.key,
+                    spl_vault_associated_address.key,
+                    nft_vault.key,
+                    &[nft_vault.key],
+                    coinflip.amount,
+                    9
+                )?,&[
+                    token_program_id.clone(),
+                    spl_token_mint.clone(),
+                    spl_vault_associated_address.clone(),
+                    nft_vault.clone(),
+                    nft_vault.clone(),
+                    system_program.clone(),
+                    rent_info.clone()
+                ],&[nft_vault_signer_seeds,spl_token_signer_seeds]
+            )?;
+            msg!("Transfering tokens ....");
+
+            transfer_tokens(
+                token_program_id,
+                spl_vault_associated_address,
+                spl_token_mint,
+                buyer_spl_associated,
+                nft_vault,
+                accounts,
+                coinflip.amount,
+                &[nft_vault_signer_seeds],
+            )?;
+            msg!("Transfering SOL to owner");
+            let amount = coinflip.amount
+                .checked_mul(pda_check.price)
+                .ok_or(TokenError::Overflow)?;
+            let fee = config.fee_for(amount)?;
+            msg!("Protocol fee {}",fee);
+            quote.transfer(player_quote, player, treasury, fee, None)?;
+            let royalty = pay_royalties(&quote, player_quote, player, creators, None, &royalties, amount - fee)?;
+            let payee = if proceeds.is_some() { proceeds_quote } else { owner_quote };
+            pay_curator(&quote, player_quote, player, payee, &mut proceeds, None, amount - fee - royalty)?;
+            record_protocol_fee(&mut pda_check, &mut config, fee)?;
+            record_royalty(&mut pda_check, royalty)?;
+        pda_check.number_of_tokens=pda_check.number_of_tokens
+            .checked_add(coinflip.amount)
+            .ok_or(TokenError::Overflow)?;
+        pda_check.pending_flips = pda_check.pending_flips.saturating_sub(1);
+        coinflip.won=0;
+        }
+        else if coinflip.address == *player.key
+        {
+            msg!("You have already claimed");
+            return Err(TokenError::AlreadyClaimed.into());
+        }
+        else
+        {
+            msg!("You haven't won");
+            return Err(TokenError::NotCoinFlipWinner.into());
+        }
+
+        coinflip.save(coinflip_pda)?;
+        pda_check.save(pda)?;
+        config.save(config_info)?;
+        if let Some(proceeds) = proceeds {
+            proceeds.save(proceeds_info)?;
+        }
+
+        Ok(())
+    }
+    pub fn process_migrate_vault(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //rewrites a vault written with the legacy layout into the current layout
+        let MigrateVaultAccounts{
+            payer,
+            pda,
+            system_program,
+        } = MigrateVaultAccounts::validate(program_id, accounts)?;
+
+        let escrow = NftDetails::upgrade(&pda.data.borrow())?;
+
+        let rent = Rent::get()?;
+        let required_lamports = rent.minimum_balance(NftDetails::LEN);
+        if pda.lamports() < required_lamports
+        {
+            msg!("Topping up rent");
+            invoke(
+                &system_instruction::transfer(
+                    payer.key,
+                    pda.key,
+                    required_lamports.saturating_sub(pda.lamports()),
+                ),
+                &[
+                    payer.clone(),
+                    pda.clone(),
+                    system_program.clone()
+                ],
+            )?;
+        }
+        pda.realloc(NftDetails::LEN, true)?;
+        assert_rent_exempt(pda, &rent)?;
+        escrow.save(pda)?;
+        msg!("Vault migrated to version {}",NftDetails::VERSION);
+        Ok(())
+    }
+    pub fn process_init_config(program_id: &Pubkey,accounts: &[AccountInfo],fee_bps: u16, treasury: Pubkey)-> ProgramResult {
+        //creating the global protocol config
+        let InitConfigAccounts{
+            authority,
+            config_info,
+            system_program,
+            config_bump,
+            ..
+        } = InitConfigAccounts::validate(program_id, accounts)?;
+
+        if fee_bps > MAX_FEE_BPS
+        {
+            return Err(TokenError::InvalidFee.into());
+        }
+        let config_signer_seeds: &[&[_]] = &[
+            CONFIGPREFIX.as_bytes(),
+            &[config_bump],
+        ];
+        let rent = Rent::get()?;
+        create_account_signed(
+            authority,
+            rent.minimum_balance(Config::LEN),
+            Config::LEN,
+            program_id,
+            system_program,
+            config_info,
+            config_signer_seeds,
+        )?;
+        assert_rent_exempt(config_info, &rent)?;
+        let config = Config{
+            account_type: AccountType::Config,
+            version: Config::VERSION,
+            authority: *authority.key,
+            treasury,
+            fee_bps,
+            total_fees: 0,
+        };
+        config.save(config_info)?;
+        Ok(())
+    }
+    pub fn process_update_config(program_id: &Pubkey,accounts: &[AccountInfo],fee_bps: u16, treasury: Pubkey, authority: Pubkey)-> ProgramResult {
+        let UpdateConfigAccounts{
+            config_info,
+            mut config,
+            ..
+        } = UpdateConfigAccounts::validate(program_id, accounts)?;
+
+        if fee_bps > MAX_FEE_BPS
+        {
+            return Err(TokenError::InvalidFee.into());
+        }
+        config.fee_bps = fee_bps;
+        config.treasury = treasury;
+        config.authority = authority;
+        config.save(config_info)?;
+        Ok(())
+    }
+    pub fn process_withdraw_proceeds(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //a beneficiary withdraws the proceeds accrued to them
+        let WithdrawProceedsAccounts{
+            beneficiary,
+            nft_owner,
+            pda,
+            proceeds_info,
+            proceeds_quote,
+            beneficiary_quote,
+            mut proceeds,
+            quote,
+            proceeds_bump,
+        } = WithdrawProceedsAccounts::validate(program_id, accounts)?;
+
+        let entry = proceeds.find_mut(beneficiary.key).ok_or(TokenError::NotBeneficiary)?;
+        let amount = entry.balance;
+        entry.balance = 0;
+        let proceeds_signer_seeds: &[&[_]] = &[
+            PROCEEDSPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[proceeds_bump],
+        ];
+        //SOL proceeds are debited directly since the proceeds account is owned by the program
+        quote.withdraw(proceeds_quote, proceeds_info, beneficiary_quote, amount, proceeds_signer_seeds)?;
+        proceeds.save(proceeds_info)?;
+        msg!("Withdrawn {}",amount);
+        Ok(())
+    }
+    pub fn process_init_stake_pool(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //creating the pool fraction holders stake into
+        let InitStakePoolAccounts{
+            payer,
+            nft_owner,
+            pda,
+            stake_pool_info,
+            spl_token_mint,
+            pool_token_account,
+            token_program_id,
+            quote_token_program,
+            associated_token_info,
+            system_program,
+            rent_info,
+            quote_mint_info,
+            pool_quote,
+            stake_pool_bump,
+        } = InitStakePoolAccounts::validate(program_id, accounts)?;
+
+        let stake_pool_signer_seeds: &[&[_]] = &[
+            STAKEPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[stake_pool_bump],
+        ];
+        let rent = Rent::get()?;
+        create_account_signed(
+            payer,
+            rent.minimum_balance(StakePool::LEN),
+            StakePool::LEN,
+            program_id,
+            system_program,
+            stake_pool_info,
+            stake_pool_signer_seeds,
+        )?;
+        assert_rent_exempt(stake_pool_info, &rent)?;
+        if pool_token_account.data_is_empty()
+        {
+            invoke(
+                &spl_associated_token_account::instruction::create_associated_token_account(
+                    payer.key,
+                    stake_pool_info.key,
+                    spl_token_mint.key,
+                    token_program_id.key,
+                ),&[
+                    payer.clone(),
+                    pool_token_account.clone(),
+                    stake_pool_info.clone(),
+                    spl_token_mint.clone(),
+                    token_program_id.clone(),
+                    rent_info.clone(),
+                    associated_token_info.clone(),
+                    system_program.clone()
+                ]
+            )?;
+        }
+        //auction proceeds in an SPL quote mint are paid to the pool's associated account
+        if let (Some(quote_mint_info), Some(pool_quote)) = (quote_mint_info, pool_quote) {
+            if pool_quote.data_is_empty()
+            {
+                invoke(
+                    &spl_associated_token_account::instruction::create_associated_token_account(
+                        payer.key,
+                        stake_pool_info.key,
+                        quote_mint_info.key,
+                        quote_token_program.key,
+                    ),&[
+                        payer.clone(),
+                        pool_quote.clone(),
+                        stake_pool_info.clone(),
+                        quote_mint_info.clone(),
+                        quote_token_program.clone(),
+                        rent_info.clone(),
+                        associated_token_info.clone(),
+                        system_program.clone()
+                    ]
+                )?;
+            }
         }
-        let spl_vault_address=get_associated_token_address(nft_vault.key,spl_token_mint.key);
-        if  *spl_vault_associated_address.key!=spl_vault_address
+        let stake_pool = StakePool{
+            account_type: AccountType::StakePool,
+            version: StakePool::VERSION,
+            vault: *pda.key,
+            total_staked: 0,
+            reward_per_token: 0,
+            total_rewards: 0,
+        };
+        stake_pool.save(stake_pool_info)?;
+        Ok(())
+    }
+    pub fn process_stake(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64)-> ProgramResult {
+        let StakeAccounts{
+            staker,
+            stake_pool_info,
+            stake_entry_info,
+            staker_token_account,
+            pool_token_account,
+            spl_token_mint,
+            token_program_id,
+            system_program,
+            mut stake_pool,
+            stake_entry,
+            stake_entry_bump,
+            ..
+        } = StakeAccounts::validate(program_id, accounts)?;
+
+        let mut stake_entry = match stake_entry {
+            Some(stake_entry) => stake_entry,
+            None => {
+                let stake_entry_signer_seeds: &[&[_]] = &[
+                    STAKEENTRYPREFIX.as_bytes(),
+                    &staker.key.to_bytes(),
+                    &stake_pool_info.key.to_bytes(),
+                    &[stake_entry_bump],
+                ];
+                let rent = Rent::get()?;
+                create_account_signed(
+                    staker,
+                    rent.minimum_balance(StakeEntry::LEN),
+                    StakeEntry::LEN,
+                    program_id,
+                    system_program,
+                    stake_entry_info,
+                    stake_entry_signer_seeds,
+                )?;
+                assert_rent_exempt(stake_entry_info, &rent)?;
+                StakeEntry{
+                    account_type: AccountType::StakeEntry,
+                    version: StakeEntry::VERSION,
+                    pool: *stake_pool_info.key,
+                    owner: *staker.key,
+                    amount: 0,
+                    reward_per_token_paid: stake_pool.reward_per_token,
+                    pending: 0,
+                    staked_at: 0,
+                }
+            }
+        };
+        stake_entry.settle(&stake_pool)?;
+        //a transfer fee of the fraction mint is withheld from the pool, only what arrived is staked
+        let pool_balance = get_token_balance(pool_token_account)?;
+        transfer_tokens(
+            token_program_id,
+            staker_token_account,
+            spl_token_mint,
+            pool_token_account,
+            staker,
+            accounts,
+            amount,
+            &[],
+        )?;
+        let amount = get_token_balance(pool_token_account)? - pool_balance;
+        stake_entry.add_stake(current_timestamp()?, amount)?;
+        stake_pool.total_staked = stake_pool.total_staked.checked_add(amount).ok_or(TokenError::Overflow)?;
+        stake_entry.save(stake_entry_info)?;
+        stake_pool.save(stake_pool_info)?;
+        msg!("Staked {}",amount);
+        Ok(())
+    }
+    pub fn process_unstake(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64)-> ProgramResult {
+        let StakeAccounts{
+            nft_owner,
+            pda,
+            stake_pool_info,
+            stake_entry_info,
+            staker_token_account,
+            pool_token_account,
+            spl_token_mint,
+            token_program_id,
+            mut stake_pool,
+            stake_entry,
+            stake_pool_bump,
+            ..
+        } = StakeAccounts::validate(program_id, accounts)?;
+
+        let mut stake_entry = stake_entry.ok_or(TokenError::InvalidStakeEntry)?;
+        if amount > stake_entry.amount
+        {
+            msg!("Only {} tokens are staked",stake_entry.amount);
+            return Err(TokenError::InsufficientStake.into());
+        }
+        stake_entry.settle(&stake_pool)?;
+        let stake_pool_signer_seeds: &[&[_]] = &[
+            STAKEPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[stake_pool_bump],
+        ];
+        transfer_tokens(
+            token_program_id,
+            pool_token_account,
+            spl_token_mint,
+            staker_token_account,
+            stake_pool_info,
+            accounts,
+            amount,
+            &[stake_pool_signer_seeds],
+        )?;
+        stake_entry.amount -= amount;
+        stake_pool.total_staked = stake_pool.total_staked.checked_sub(amount).ok_or(TokenError::Overflow)?;
+        stake_entry.save(stake_entry_info)?;
+        stake_pool.save(stake_pool_info)?;
+        msg!("Unstaked {}",amount);
+        Ok(())
+    }
+    pub fn process_claim_stake_rewards(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        let StakeAccounts{
+            nft_owner,
+            pda,
+            stake_pool_info,
+            stake_entry_info,
+            pool_quote,
+            staker_quote,
+            stake_pool,
+            stake_entry,
+            stake_pool_bump,
+            quote,
+            ..
+        } = StakeAccounts::validate(program_id, accounts)?;
+
+        let mut stake_entry = stake_entry.ok_or(TokenError::InvalidStakeEntry)?;
+        stake_entry.settle(&stake_pool)?;
+        let reward = stake_entry.pending;
+        stake_entry.pending = 0;
+        let stake_pool_signer_seeds: &[&[_]] = &[
+            STAKEPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[stake_pool_bump],
+        ];
+        //SOL rewards are debited directly since the stake pool is owned by the program
+        quote.withdraw(pool_quote, stake_pool_info, staker_quote, reward, stake_pool_signer_seeds)?;
+        stake_entry.save(stake_entry_info)?;
+        msg!("Claimed {}",reward);
+        Ok(())
+    }
+    pub fn process_create_proposal(program_id: &Pubkey,accounts: &[AccountInfo],reserve_price: u64, auction_interval: u64, inflation_bps: u16)-> ProgramResult {
+        //proposing new vault parameters to the fraction holders
+        let CreateProposalAccounts{
+            proposer,
+            pda,
+            proposal_info,
+            system_program,
+            spl_token_mint,
+            spl_vault_associated_address,
+            ..
+        } = CreateProposalAccounts::validate(program_id, accounts)?;
+
+        if auction_interval == 0 || inflation_bps > MAX_INFLATION_BPS
+        {
+            return Err(TokenError::InvalidProposal.into());
+        }
+        //fractions still held by the vault can never vote
+        let (_decimals, supply) = get_mint_state(spl_token_mint)?;
+        let circulating = supply
+            .checked_sub(get_token_balance(spl_vault_associated_address)?)
+            .ok_or(TokenError::Overflow)?;
+        let now = current_timestamp()?;
+        let voting_ends_at = now.checked_add(VOTING_PERIOD).ok_or(TokenError::Overflow)?;
+        let rent = Rent::get()?;
+        create_pda_account(
+            proposer,
+            rent.minimum_balance(Proposal::LEN),
+            Proposal::LEN,
+            program_id,
+            system_program,
+            proposal_info
+        )?;
+        assert_rent_exempt(proposal_info, &rent)?;
+        let proposal = Proposal{
+            account_type: AccountType::Proposal,
+            version: Proposal::VERSION,
+            vault: *pda.key,
+            proposer: *proposer.key,
+            reserve_price,
+            auction_interval,
+            inflation_bps,
+            votes_for: 0,
+            votes_against: 0,
+            quorum: (circulating as u128 * QUORUM_BPS as u128 / 10000) as u64,
+            voting_ends_at,
+            executable_at: voting_ends_at.checked_add(TIMELOCK).ok_or(TokenError::Overflow)?,
+            executed: false,
+        };
+        proposal.save(proposal_info)?;
+        Ok(())
+    }
+    pub fn process_cast_vote(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64, support: bool)-> ProgramResult {
+        //the voted tokens are escrowed until voting ends so they can't vote twice
+        let CastVoteAccounts{
+            voter,
+            proposal_info,
+            vote_info,
+            spl_token_mint,
+            voter_token_account,
+            vote_token_account,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            mut proposal,
+            vote_bump,
+            ..
+        } = CastVoteAccounts::validate(program_id, accounts)?;
+
+        if !proposal.voting_open(current_timestamp()?)
+        {
+            return Err(TokenError::VotingClosed.into());
+        }
+        let vote_signer_seeds: &[&[_]] = &[
+            VOTEPREFIX.as_bytes(),
+            &voter.key.to_bytes(),
+            &proposal_info.key.to_bytes(),
+            &[vote_bump],
+        ];
+        let rent = Rent::get()?;
+        create_account_signed(
+            voter,
+            rent.minimum_balance(Vote::LEN),
+            Vote::LEN,
+            program_id,
+            system_program,
+            vote_info,
+            vote_signer_seeds,
+        )?;
+        assert_rent_exempt(vote_info, &rent)?;
+        if vote_token_account.data_is_empty()
+        {
+            invoke(
+                &spl_associated_token_account::instruction::create_associated_token_account(
+                    voter.key,
+                    vote_info.key,
+                    spl_token_mint.key,
+                    token_program_id.key,
+                ),&[
+                    voter.clone(),
+                    vote_token_account.clone(),
+                    vote_info.clone(),
+                    spl_token_mint.clone(),
+                    token_program_id.clone(),
+                    rent_info.clone(),
+                    associated_token_info.clone(),
+                    system_program.clone()
+                ]
+            )?;
+        }
+        //votes count what arrived in escrow, after any transfer fee of the fraction mint
+        let escrowed = get_token_balance(vote_token_account)?;
+        transfer_tokens(
+            token_program_id,
+            voter_token_account,
+            spl_token_mint,
+            vote_token_account,
+            voter,
+            accounts,
+            amount,
+            &[],
+        )?;
+        let amount = get_token_balance(vote_token_account)? - escrowed;
+        if support
+        {
+            proposal.votes_for = proposal.votes_for.checked_add(amount).ok_or(TokenError::Overflow)?;
+        }
+        else
+        {
+            proposal.votes_against = proposal.votes_against.checked_add(amount).ok_or(TokenError::Overflow)?;
+        }
+        let vote = Vote{
+            account_type: AccountType::Vote,
+            version: Vote::VERSION,
+            proposal: *proposal_info.key,
+            voter: *voter.key,
+            amount,
+            support,
+        };
+        vote.save(vote_info)?;
+        proposal.save(proposal_info)?;
+        Ok(())
+    }
+    pub fn process_execute_proposal(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //applies a passed proposal once its timelock is over
+        let ExecuteProposalAccounts{
+            pda,
+            proposal_info,
+            mut escrow,
+            mut proposal,
+        } = ExecuteProposalAccounts::validate(program_id, accounts)?;
+
+        let now = current_timestamp()?;
+        if proposal.executed
+        {
+            return Err(TokenError::ProposalExecuted.into());
+        }
+        if proposal.timelocked(now)
+        {
+            msg!("The proposal is executable at {}",proposal.executable_at);
+            return Err(TokenError::TimelockActive.into());
+        }
+        if !proposal.passed()
+        {
+            return Err(TokenError::ProposalNotPassed.into());
+        }
+        escrow.reserve_price = proposal.reserve_price;
+        escrow.inflation_bps = proposal.inflation_bps;
+        if proposal.auction_interval != escrow.auction_interval
+        {
+            escrow.set_auction_interval(now, proposal.auction_interval)?;
+        }
+        proposal.executed = true;
+        escrow.save(pda)?;
+        proposal.save(proposal_info)?;
+        msg!("Proposal executed");
+        Ok(())
+    }
+    pub fn process_withdraw_vote(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        let WithdrawVoteAccounts{
+            voter,
+            proposal_info,
+            vote_info,
+            voter_token_account,
+            vote_token_account,
+            token_program_id,
+            spl_token_mint,
+            mut vote,
+            voting_closed,
+            vote_bump,
+        } = WithdrawVoteAccounts::validate(program_id, accounts, current_timestamp()?)?;
+
+        if !voting_closed
+        {
+            msg!("Voting is still running");
+            return Err(TokenError::TimelockActive.into());
+        }
+        let vote_signer_seeds: &[&[_]] = &[
+            VOTEPREFIX.as_bytes(),
+            &voter.key.to_bytes(),
+            &proposal_info.key.to_bytes(),
+            &[vote_bump],
+        ];
+        transfer_tokens(
+            token_program_id,
+            vote_token_account,
+            spl_token_mint,
+            voter_token_account,
+            vote_info,
+            accounts,
+            vote.amount,
+            &[vote_signer_seeds],
+        )?;
+        vote.amount = 0;
+        vote.save(vote_info)?;
+        Ok(())
+    }
+    pub fn process_create_buyout(program_id: &Pubkey,accounts: &[AccountInfo],price: u64)-> ProgramResult {
+        //escrowing an offer for the whole nft
+        let CreateBuyoutAccounts{
+            bidder,
+            pda,
+            buyout_info,
+            system_program,
+            spl_token_mint,
+            spl_vault_associated_address,
+            quote_token_program,
+            associated_token_info,
+            rent_info,
+            quote_mint_info,
+            bidder_quote,
+            buyout_quote,
+            escrow,
+            quote,
+            buyout_bump,
+        } = CreateBuyoutAccounts::validate(program_id, accounts)?;
+
+        if escrow.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        if escrow.raise_failed(current_timestamp()?)?
+        {
+            return Err(TokenError::RaiseFailed.into());
+        }
+        let valuation = escrow.implied_valuation()?;
+        if price < valuation
+        {
+            msg!("The offer is below the implied valuation {}",valuation);
+            return Err(TokenError::BuyoutBelowValuation.into());
+        }
+        //fractions still held by the vault can never accept the offer
+        let (_decimals, supply) = get_mint_state(spl_token_mint)?;
+        let circulating = supply
+            .checked_sub(get_token_balance(spl_vault_associated_address)?)
+            .ok_or(TokenError::Overflow)?;
+        let buyout_signer_seeds: &[&[_]] = &[
+            BUYOUTPREFIX.as_bytes(),
+            &bidder.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[buyout_bump],
+        ];
+        let rent = Rent::get()?;
+        create_account_signed(
+            bidder,
+            rent.minimum_balance(Buyout::LEN),
+            Buyout::LEN,
+            program_id,
+            system_program,
+            buyout_info,
+            buyout_signer_seeds,
+        )?;
+        assert_rent_exempt(buyout_info, &rent)?;
+        //an offer in an SPL quote mint is escrowed in the associated account of the buyout
+        if let Some(quote_mint_info) = quote_mint_info.filter(|_| !quote.is_native()) {
+            if buyout_quote.data_is_empty()
+            {
+                invoke(
+                    &spl_associated_token_account::instruction::create_associated_token_account(
+                        bidder.key,
+                        buyout_info.key,
+                        quote_mint_info.key,
+                        quote_token_program.key,
+                    ),&[
+                        bidder.clone(),
+                        buyout_quote.clone(),
+                        buyout_info.clone(),
+                        quote_mint_info.clone(),
+                        quote_token_program.clone(),
+                        rent_info.clone(),
+                        associated_token_info.clone(),
+                        system_program.clone()
+                    ]
+                )?;
+            }
+        }
+        quote.transfer(bidder_quote, bidder, buyout_quote, price, None)?;
+        let buyout = Buyout{
+            account_type: AccountType::Buyout,
+            version: Buyout::VERSION,
+            vault: *pda.key,
+            bidder: *bidder.key,
+            price,
+            votes_for: 0,
+            threshold: Buyout::threshold_for(circulating),
+            deadline: current_timestamp()?.checked_add(BUYOUT_PERIOD).ok_or(TokenError::Overflow)?,
+            status: BuyoutStatus::Open,
+            redeemable_supply: 0,
+            redeemed: 0,
+        };
+        buyout.save(buyout_info)?;
+        Ok(())
+    }
+    pub fn process_cast_buyout_vote(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64)-> ProgramResult {
+        //accepting a buyout by escrowing fractions
+        let BuyoutVoteAccounts{
+            voter,
+            buyout_info,
+            vote_info,
+            spl_token_mint,
+            voter_token_account,
+            vote_token_account,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            mut buyout,
+            vote_bump,
+            ..
+        } = BuyoutVoteAccounts::validate(program_id, accounts)?;
+
+        if buyout.status != BuyoutStatus::Open || current_timestamp()? >= buyout.deadline
+        {
+            return Err(TokenError::BuyoutClosed.into());
+        }
+        let vote_signer_seeds: &[&[_]] = &[
+            VOTEPREFIX.as_bytes(),
+            &voter.key.to_bytes(),
+            &buyout_info.key.to_bytes(),
+            &[vote_bump],
+        ];
+        let rent = Rent::get()?;
+        create_account_signed(
+            voter,
+            rent.minimum_balance(Vote::LEN),
+            Vote::LEN,
+            program_id,
+            system_program,
+            vote_info,
+            vote_signer_seeds,
+        )?;
+        assert_rent_exempt(vote_info, &rent)?;
+        if vote_token_account.data_is_empty()
+        {
+            invoke(
+                &spl_associated_token_account::instruction::create_associated_token_account(
+                    voter.key,
+                    vote_info.key,
+                    spl_token_mint.key,
+                    token_program_id.key,
+                ),&[
+                    voter.clone(),
+                    vote_token_account.clone(),
+                    vote_info.clone(),
+                    spl_token_mint.clone(),
+                    token_program_id.clone(),
+                    rent_info.clone(),
+                    associated_token_info.clone(),
+                    system_program.clone()
+                ]
+            )?;
+        }
+        //votes count what arrived in escrow, after any transfer fee of the fraction mint
+        let escrowed = get_token_balance(vote_token_account)?;
+        transfer_tokens(
+            token_program_id,
+            voter_token_account,
+            spl_token_mint,
+            vote_token_account,
+            voter,
+            accounts,
+            amount,
+            &[],
+        )?;
+        let amount = get_token_balance(vote_token_account)? - escrowed;
+        buyout.votes_for = buyout.votes_for.checked_add(amount).ok_or(TokenError::Overflow)?;
+        let vote = Vote{
+            account_type: AccountType::Vote,
+            version: Vote::VERSION,
+            proposal: *buyout_info.key,
+            voter: *voter.key,
+            amount,
+            support: true,
+        };
+        vote.save(vote_info)?;
+        buyout.save(buyout_info)?;
+        Ok(())
+    }
+    pub fn process_execute_buyout(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //hands the nft to the bidder of an accepted buyout
+        let ExecuteBuyoutAccounts{
+            payer,
+            bidder,
+            nft_owner,
+            pda,
+            buyout_info,
+            nft_vault,
+            nft_associated_address,
+            bidder_nft_account,
+            nft_mint,
+            spl_token_mint,
+            spl_vault_associated_address,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            mut escrow,
+            mut buyout,
+            nft_vault_bump,
+        } = ExecuteBuyoutAccounts::validate(program_id, accounts)?;
+
+        if escrow.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        if buyout.status != BuyoutStatus::Open
+        {
+            return Err(TokenError::BuyoutClosed.into());
+        }
+        if !buyout.accepted()
+        {
+            msg!("{} of {} fractions accepted",buyout.votes_for,buyout.threshold);
+            return Err(TokenError::BuyoutOpen.into());
+        }
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[nft_vault_bump],
+        ];
+        if bidder_nft_account.data_is_empty()
+        {
+            invoke(
+                &spl_associated_token_account::instruction::create_associated_token_account(
+                    payer.key,
+                    bidder.key,
+                    nft_mint.key,
+                    token_program_id.key,
+                ),&[
+                    payer.clone(),
+                    bidder_nft_account.clone(),
+                    bidder.clone(),
+                    nft_mint.clone(),
+                    token_program_id.clone(),
+                    rent_info.clone(),
+                    associated_token_info.clone(),
+                    system_program.clone()
+                ]
+            )?;
+        }
+        transfer_tokens(
+            token_program_id,
+            nft_associated_address,
+            nft_mint,
+            bidder_nft_account,
+            nft_vault,
+            accounts,
+            1,
+            &[nft_vault_signer_seeds],
+        )?;
+        //fractions still held by the vault don't share the price, the ones owed to the winners of open auctions
+        //do and are minted when those auctions settle. Unclaimed coin flips lapse, they were never paid for
+        let (_decimals, supply) = get_mint_state(spl_token_mint)?;
+        buyout.redeemable_supply = supply
+            .checked_sub(get_token_balance(spl_vault_associated_address)?)
+            .and_then(|circulating| circulating.checked_add(escrow.unsettled_auction_tokens(supply)))
+            .ok_or(TokenError::Overflow)?;
+        buyout.status = BuyoutStatus::Accepted;
+        escrow.bought_out = true;
+        buyout.save(buyout_info)?;
+        escrow.save(pda)?;
+        msg!("Buyout accepted, {} fractions can be redeemed",buyout.redeemable_supply);
+        Ok(())
+    }
+    pub fn process_refund_buyout(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        let RefundBuyoutAccounts{
+            bidder,
+            pda,
+            buyout_info,
+            buyout_quote,
+            bidder_quote,
+            escrow,
+            mut buyout,
+            quote,
+            buyout_bump,
+            ..
+        } = RefundBuyoutAccounts::validate(program_id, accounts)?;
+
+        if buyout.status != BuyoutStatus::Open
         {
-            msg!("SPL token account of the vault doesn't matches");
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(TokenError::BuyoutClosed.into());
         }
-        if token_balance < (pda_check.number_of_tokens*2)/3
+        if !buyout.refundable(current_timestamp()?, escrow.bought_out)
         {
-            msg!("You don't have enough tokens");
-            return Err(ProgramError::MissingRequiredSignature);   
+            return Err(TokenError::BuyoutOpen.into());
         }
-       //creating coinflip account
-        create_pda_account( 
-            player,
-            transfer_amount,
-            std::mem::size_of::<CoinFlip>(),
-            program_id,
-            system_program,
-            coinflip_pda
-        )?;
+        let buyout_signer_seeds: &[&[_]] = &[
+            BUYOUTPREFIX.as_bytes(),
+            &bidder.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[buyout_bump],
+        ];
+        quote.withdraw(buyout_quote, buyout_info, bidder_quote, buyout.price, buyout_signer_seeds)?;
+        buyout.status = BuyoutStatus::Refunded;
+        buyout.save(buyout_info)?;
+        msg!("Refunded {}",buyout.price);
+        Ok(())
+    }
+    pub fn process_redeem_buyout(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64)-> ProgramResult {
+        //burns fractions for their share of the buyout price
+        let RedeemBuyoutAccounts{
+            holder,
+            pda,
+            buyout_info,
+            spl_token_mint,
+            holder_token_account,
+            token_program_id,
+            buyout_quote,
+            holder_quote,
+            mut buyout,
+            quote,
+            buyout_bump,
+            ..
+        } = RedeemBuyoutAccounts::validate(program_id, accounts)?;
 
-        let mut coinflip = CoinFlip::try_from_slice(&coinflip_pda.data.borrow())?;
-        msg!("Transfering token ....");
+        if buyout.status != BuyoutStatus::Accepted
+        {
+            return Err(TokenError::BuyoutOpen.into());
+        }
+        let payout = buyout.redeem(amount)?;
         invoke(
-            &spl_token::instruction::transfer(
+            &spl_token_2022::instruction::burn(
                 token_program_id.key,
-                player_associated_token.key,
-                spl_vault_associated_address.key,
-                player.key,
-                &[player.key],
-                token_balance/1000,
+                holder_token_account.key,
+                spl_token_mint.key,
+                holder.key,
+                &[holder.key],
+                amount,
             )?,
             &[
                 token_program_id.clone(),
-                player_associated_token.clone(),
-                spl_vault_associated_address.clone(),
-                player.clone(),
-                system_program.clone()
+                holder_token_account.clone(),
+                spl_token_mint.clone(),
+                holder.clone(),
             ],
         )?;
+        let buyout_signer_seeds: &[&[_]] = &[
+            BUYOUTPREFIX.as_bytes(),
+            &buyout.bidder.to_bytes(),
+            &pda.key.to_bytes(),
+            &[buyout_bump],
+        ];
+        quote.withdraw(buyout_quote, buyout_info, holder_quote, payout, buyout_signer_seeds)?;
+        buyout.save(buyout_info)?;
+        msg!("Redeemed {} fractions for {}",amount,payout);
+        Ok(())
+    }
+    pub fn process_claim_sale_refund(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //refunding a dutch sale buyer down to the clearing price and paying the rest of its premium to the vault
+        let ClaimSaleRefundAccounts{
+            nft_owner,
+            pda,
+            nft_vault,
+            purchase_info,
+            config_info,
+            treasury,
+            proceeds_info,
+            vault_quote,
+            buyer_quote,
+            owner_quote,
+            proceeds_quote,
+            creators,
+            mut escrow,
+            mut config,
+            mut proceeds,
+            royalties,
+            quote,
+            mut purchase,
+            nft_vault_bump,
+            ..
+        } = ClaimSaleRefundAccounts::validate(program_id, accounts)?;
 
-        msg!("Flipping the Coin");
-        if now % 2 == 0 {
-            msg!("You have lost");
-            coinflip.won = 0
+        if !escrow.sale_refunds
+        {
+            return Err(TokenError::InvalidSaleMode.into());
         }
-        else 
+        if escrow.day_at(current_timestamp()?)? == 0
         {
-            msg!("You have won, claim the reward");
-            coinflip.won = 1;
-            coinflip.address=*player.key;
-            coinflip.amount=pda_check.number_of_tokens/100; //1% of total tokens
+            msg!("Refunds open once the buy period ended");
+            return Err(TokenError::SaleOpen.into());
+        }
+        if purchase.premium == 0
+        {
+            return Err(TokenError::AlreadyClaimed.into());
+        }
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[nft_vault_bump],
+        ];
+        let owed = purchase.owed_premium(escrow.clearing_price, escrow.price)?;
+        let refund = purchase.premium - owed;
+        quote.transfer(vault_quote, nft_vault, buyer_quote, refund, Some(nft_vault_signer_seeds))?;
+        let fee = config.fee_for(owed)?;
+        quote.transfer(vault_quote, nft_vault, treasury, fee, Some(nft_vault_signer_seeds))?;
+        let royalty = pay_royalties(&quote, vault_quote, nft_vault, creators, Some(nft_vault_signer_seeds), &royalties, owed - fee)?;
+        let payee = if proceeds.is_some() { proceeds_quote } else { owner_quote };
+        pay_curator(&quote, vault_quote, nft_vault, payee, &mut proceeds, Some(nft_vault_signer_seeds), owed - fee - royalty)?;
+        purchase.premium = 0;
+        purchase.save(purchase_info)?;
+        record_protocol_fee(&mut escrow, &mut config, fee)?;
+        record_royalty(&mut escrow, royalty)?;
+        escrow.save(pda)?;
+        config.save(config_info)?;
+        if let Some(proceeds) = proceeds {
+            proceeds.save(proceeds_info)?;
         }
-        coinflip.serialize(&mut &mut coinflip_pda.data.borrow_mut()[..])?;
+        msg!("Refunded {} at a clearing price of {}",refund,escrow.clearing_price);
         Ok(())
     }
-    pub fn process_coin_flip_claim(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
-        msg!("Inside the function");
-        let account_info_iter = &mut accounts.iter();
-        let player =  next_account_info(account_info_iter)?; // sender or signer
-        let nft_owner = next_account_info(account_info_iter)?; // auction creator
-        let pda = next_account_info(account_info_iter)?; // pda data that consists number of tokens , auction created
-        let coinflip_pda = next_account_info(account_info_iter)?; // pda data for coinflip
-        let token_program_id = next_account_info(account_info_iter)?; //TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA
-        let nft_vault = next_account_info(account_info_iter)?; // nft vault
-        let spl_vault_associated_address = next_account_info(account_info_iter)?;  // find associated address from nft vault and spl token mint
-        let buyer_spl_associated =  next_account_info(account_info_iter)?; // sender or signer
-        let spl_token_mint=next_account_info(account_info_iter)?;
-        let system_program = next_account_info(account_info_iter)?; 
-        let rent_info=next_account_info(account_info_iter)?; 
-
-        if !player.is_signer
-        {
-            msg!("The player is not the signer");
-            return Err(ProgramError::MissingRequiredSignature);
-
-        }
-        msg!("PDA deserialization");
-        let  mut pda_check = NftDetails::try_from_slice(&pda.data.borrow())?;
-        msg!("Coinflip Deserialization");
-        let mut coinflip = CoinFlip::try_from_slice(&coinflip_pda.data.borrow())?;
-        msg!("Deserialization Complete");
-        if pda.owner !=program_id && coinflip_pda.owner!=program_id
-        {
-            msg!("The owner of pda or coinflip pda doesn't match");
-            return Err(ProgramError::MissingRequiredSignature);   
-        }
-        let (nft_vault_address, bump_seed) = generate_pda_and_bump_seed(
-            NFTPREFIX,
-            nft_owner.key,
-            pda.key,
-            program_id
-        );
-        if nft_vault_address != *nft_vault.key
+    pub fn process_sell_to_curve(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64,min_payout: u64)-> ProgramResult {
+        //selling fractions back along the bonding curve, paid out of the sale reserve
+        let SellToCurveAccounts{
+            seller,
+            pda,
+            nft_vault,
+            spl_token_mint,
+            seller_token_account,
+            spl_vault_associated_address,
+            token_program_id,
+            vault_quote,
+            seller_quote,
+            mut escrow,
+            quote,
+            nft_vault_bump,
+            nft_owner,
+            ..
+        } = SellToCurveAccounts::validate(program_id, accounts)?;
+
+        //a crowdfund refunds what every buyer paid, selling into its reserve would leave later refunds short
+        if !escrow.is_curve() || escrow.min_raise > 0
+        {
+            return Err(TokenError::InvalidSaleMode.into());
+        }
+        if escrow.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        if escrow.day_at(current_timestamp()?)? > 0 {
+            msg!("The buying period has ended you can only auction now");
+            return Err(TokenError::AuctionStarted.into());
+        }
+        //a transfer fee of the fraction mint is withheld from the vault, only what arrived is bought back
+        let vault_balance = get_token_balance(spl_vault_associated_address)?;
+        transfer_tokens(
+            token_program_id,
+            seller_token_account,
+            spl_token_mint,
+            spl_vault_associated_address,
+            seller,
+            accounts,
+            amount,
+            &[],
+        )?;
+        let received = get_token_balance(spl_vault_associated_address)? - vault_balance;
+        let payout = escrow.curve_refund(received)?;
+        if payout < min_payout
         {
-            msg!("NFT vault address doens't match");
-            return Err(ProgramError::MissingRequiredSignature);   
+            msg!("The curve only pays {}",payout);
+            return Err(TokenError::PriceLower.into());
         }
         let nft_vault_signer_seeds: &[&[_]] = &[
             NFTPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda.key.to_bytes(),
-            &[bump_seed],
+            &[nft_vault_bump],
         ];
-        let (spl_token_address, bump_seed_spl) = generate_pda_and_bump_seed(
-            SPLTOKENPREFIX,
-            nft_owner.key,
-            pda.key,
-            program_id
-        );
-        if spl_token_address!=*spl_token_mint.key
+        quote.transfer(vault_quote, nft_vault, seller_quote, payout, Some(nft_vault_signer_seeds))?;
+        escrow.remaining_token = escrow.remaining_token.checked_add(received).ok_or(TokenError::Overflow)?;
+        escrow.sale_reserve = escrow.sale_reserve.checked_sub(payout).ok_or(TokenError::Overflow)?;
+        escrow.raised = escrow.raised.saturating_sub(payout);
+        escrow.save(pda)?;
+        msg!("Sold {} back for {}",received,payout);
+        set_return_data(&payout.to_le_bytes());
+        Ok(())
+    }
+    pub fn process_quote_sale(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64,sell: bool)-> ProgramResult {
+        //returning the cost of buying or the payout of selling amount fractions right now
+        let QuoteSaleAccounts{
+            escrow,
+            ..
+        } = QuoteSaleAccounts::validate(program_id, accounts)?;
+
+        let quote = if sell {
+            if !escrow.is_curve()
+            {
+                return Err(TokenError::InvalidSaleMode.into());
+            }
+            escrow.curve_refund(amount)?
+        } else {
+            escrow.sale_cost(current_timestamp()?, amount)?
+        };
+        msg!("Quote {}",quote);
+        set_return_data(&quote.to_le_bytes());
+        Ok(())
+    }
+    pub fn process_withdraw_sale_reserve(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //paying the sale reserve out to the protocol, the creators and the curator once the sale ended
+        let WithdrawSaleReserveAccounts{
+            nft_owner,
+            pda,
+            nft_vault,
+            config_info,
+            treasury,
+            proceeds_info,
+            vault_quote,
+            owner_quote,
+            proceeds_quote,
+            creators,
+            mut escrow,
+            mut config,
+            mut proceeds,
+            royalties,
+            quote,
+            nft_vault_bump,
+            ..
+        } = WithdrawSaleReserveAccounts::validate(program_id, accounts)?;
+
+        if !escrow.escrows_sale()
         {
-            msg!("Mint address doesn't match");
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(TokenError::InvalidSaleMode.into());
         }
-        let spl_token_signer_seeds: &[&[_]] = &[
-            SPLTOKENPREFIX.as_bytes(),
+        let now = current_timestamp()?;
+        if escrow.day_at(now)? == 0
+        {
+            msg!("The reserve opens once the buy period ended");
+            return Err(TokenError::SaleOpen.into());
+        }
+        if escrow.raise_failed(now)?
+        {
+            msg!("Only {} of {} was raised",escrow.raised,escrow.min_raise);
+            return Err(TokenError::RaiseFailed.into());
+        }
+        if escrow.sale_reserve == 0
+        {
+            return Err(TokenError::AlreadyClaimed.into());
+        }
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
             &nft_owner.key.to_bytes(),
             &pda.key.to_bytes(),
-            &[bump_seed_spl],
+            &[nft_vault_bump],
         ];
-        let player_token_address= get_associated_token_address(player.key, spl_token_mint.key);
-        if  player_token_address!=*buyer_spl_associated.key
+        let reserve = escrow.sale_reserve;
+        let fee = config.fee_for(reserve)?;
+        quote.transfer(vault_quote, nft_vault, treasury, fee, Some(nft_vault_signer_seeds))?;
+        let royalty = pay_royalties(&quote, vault_quote, nft_vault, creators, Some(nft_vault_signer_seeds), &royalties, reserve - fee)?;
+        let payee = if proceeds.is_some() { proceeds_quote } else { owner_quote };
+        pay_curator(&quote, vault_quote, nft_vault, payee, &mut proceeds, Some(nft_vault_signer_seeds), reserve - fee - royalty)?;
+        escrow.sale_reserve = 0;
+        record_protocol_fee(&mut escrow, &mut config, fee)?;
+        record_royalty(&mut escrow, royalty)?;
+        escrow.save(pda)?;
+        config.save(config_info)?;
+        if let Some(proceeds) = proceeds {
+            proceeds.save(proceeds_info)?;
+        }
+        msg!("Withdrew a sale reserve of {}",reserve);
+        Ok(())
+    }
+    pub fn process_refund_crowdfund(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64)-> ProgramResult {
+        //burns fractions of a failed crowdfund for what the holder paid for them
+        let RefundCrowdfundAccounts{
+            holder,
+            nft_owner,
+            pda,
+            nft_vault,
+            spl_token_mint,
+            holder_token_account,
+            purchase_info,
+            token_program_id,
+            vault_quote,
+            holder_quote,
+            mut escrow,
+            mut purchase,
+            quote,
+            nft_vault_bump,
+            ..
+        } = RefundCrowdfundAccounts::validate(program_id, accounts)?;
+
+        if !escrow.raise_failed(current_timestamp()?)?
         {
-            msg!("Token Account doesn't matches");
-            return Err(ProgramError::MissingRequiredSignature);
+            return Err(TokenError::RaiseReached.into());
         }
-        let spl_vault_address=get_associated_token_address(nft_vault.key,spl_token_mint.key);
-        if  *spl_vault_associated_address.key!=spl_vault_address
+        //only fractions bought in the sale are refunded, minted coin flip prizes were never paid for
+        if amount == 0 || amount > purchase.bought
         {
-            msg!("SPL token account of the vault doesn't matches");
-            return Err(ProgramError::MissingRequiredSignature);
+            msg!("Only {} fractions were bought",purchase.bought);
+            return Err(TokenError::TokenFinished.into());
         }
-        if coinflip.won == 1 && coinflip.address == *player.key
+        invoke(
+            &spl_token_2022::instruction::burn(
+                token_program_id.key,
+                holder_token_account.key,
+                spl_token_mint.key,
+                holder.key,
+                &[holder.key],
+                amount,
+            )?,
+            &[
+                token_program_id.clone(),
+                holder_token_account.clone(),
+                spl_token_mint.clone(),
+                holder.clone(),
+            ],
+        )?;
+        let refund = purchase.refund_for(amount)?;
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[nft_vault_bump],
+        ];
+        quote.transfer(vault_quote, nft_vault, holder_quote, refund, Some(nft_vault_signer_seeds))?;
+        purchase.refund(amount, refund)?;
+        escrow.sale_reserve = escrow.sale_reserve.checked_sub(refund).ok_or(TokenError::Overflow)?;
+        escrow.number_of_tokens = escrow.number_of_tokens.checked_sub(amount).ok_or(TokenError::Overflow)?;
+        purchase.save(purchase_info)?;
+        escrow.save(pda)?;
+        msg!("Refunded {} fractions for {}",amount,refund);
+        Ok(())
+    }
+    pub fn process_reclaim_crowdfund_nft(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //hands the nft of a failed crowdfund back to the curator and closes the vault
+        let ReclaimCrowdfundNftAccounts{
+            nft_owner,
+            pda,
+            nft_vault,
+            nft_associated_address,
+            nft_owner_nft_associated,
+            nft_mint,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            mut escrow,
+            nft_vault_bump,
+        } = ReclaimCrowdfundNftAccounts::validate(program_id, accounts)?;
+
+        if escrow.bought_out
         {
-            msg!("The winner is verified");
-            msg!("Minting tokens");
-            invoke_signed(
-                &spl_token::instruction::mint_to_checked(
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        if !escrow.raise_failed(current_timestamp()?)?
+        {
+            return Err(TokenError::RaiseReached.into());
+        }
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[nft_vault_bump],
+        ];
+        if nft_owner_nft_associated.data_is_empty()
+        {
+            invoke(
+                &spl_associated_token_account::instruction::create_associated_token_account(
+                    nft_owner.key,
+                    nft_owner.key,
+                    nft_mint.key,
                     token_program_id.key,
-                    spl_token_mint.key,
-                    spl_vault_associated_address.key,
-                    nft_vault.key,
-                    &[&nft_vault.key],
-                    coinflip.amount,
-                    9
-                )?,&[
+                ),&[
+                    nft_owner.clone(),
+                    nft_owner_nft_associated.clone(),
+                    nft_owner.clone(),
+                    nft_mint.clone(),
                     token_program_id.clone(),
-                    spl_token_mint.clone(),
-                    spl_vault_associated_address.clone(),
-                    nft_vault.clone(),
-                    nft_vault.clone(),
-                    system_program.clone(),
-                    rent_info.clone()
-                ],&[nft_vault_signer_seeds,spl_token_signer_seeds]
+                    rent_info.clone(),
+                    associated_token_info.clone(),
+                    system_program.clone()
+                ]
             )?;
-            msg!("Transfering tokens ....");
+        }
+        transfer_tokens(
+            token_program_id,
+            nft_associated_address,
+            nft_mint,
+            nft_owner_nft_associated,
+            nft_vault,
+            accounts,
+            1,
+            &[nft_vault_signer_seeds],
+        )?;
+        //the vault closes like after a buyout, holders keep refunding from the sale reserve
+        escrow.bought_out = true;
+        escrow.save(pda)?;
+        msg!("Returned the nft of a crowdfund that raised {} of {}",escrow.raised,escrow.min_raise);
+        Ok(())
+    }
+    pub fn process_settle_unsold(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //hands the unsold fractions to the curator or burns them, so only circulating fractions count as supply
+        let SettleUnsoldAccounts{
+            payer,
+            nft_owner,
+            pda,
+            nft_vault,
+            spl_token_mint,
+            spl_vault_associated_address,
+            owner_token_account,
+            token_program_id,
+            associated_token_info,
+            system_program,
+            rent_info,
+            mut escrow,
+            nft_vault_bump,
+        } = SettleUnsoldAccounts::validate(program_id, accounts)?;
 
+        if escrow.bought_out
+        {
+            return Err(TokenError::VaultBoughtOut.into());
+        }
+        let now = current_timestamp()?;
+        if escrow.day_at(now)? == 0
+        {
+            msg!("Unsold fractions settle once the buy period ended");
+            return Err(TokenError::SaleOpen.into());
+        }
+        //the unsold fractions of a failed crowdfund must not share its refunds
+        if escrow.raise_failed(now)?
+        {
+            return Err(TokenError::RaiseFailed.into());
+        }
+        //the vault account also holds withheld fees and fractions owed elsewhere, only the unsold sale fractions settle
+        let unsold = escrow.remaining_token;
+        if unsold == 0
+        {
+            return Err(TokenError::AlreadyClaimed.into());
+        }
+        let nft_vault_signer_seeds: &[&[_]] = &[
+            NFTPREFIX.as_bytes(),
+            &nft_owner.key.to_bytes(),
+            &pda.key.to_bytes(),
+            &[nft_vault_bump],
+        ];
+        if escrow.burn_unsold
+        {
             invoke_signed(
-                &spl_token::instruction::transfer(
+                &spl_token_2022::instruction::burn(
                     token_program_id.key,
                     spl_vault_associated_address.key,
-                    buyer_spl_associated.key,
+                    spl_token_mint.key,
                     nft_vault.key,
                     &[nft_vault.key],
-                    coinflip.amount,
+                    unsold,
                 )?,
                 &[
                     token_program_id.clone(),
                     spl_vault_associated_address.clone(),
-                    buyer_spl_associated.clone(),
+                    spl_token_mint.clone(),
                     nft_vault.clone(),
-                    system_program.clone()
-                ],&[&nft_vault_signer_seeds],
+                ],
+                &[nft_vault_signer_seeds],
             )?;
-            msg!("Transfering SOL to owner");
-            invoke(
-                &system_instruction::transfer
-                (
-                    player.key, 
-                    nft_owner.key, 
-                    coinflip.amount*pda_check.price,
-                ),
-                   &[
-                       player.clone(),
-                       nft_owner.clone(),
-                       system_program.clone(),
-                   ])?;
-        pda_check.number_of_tokens+=coinflip.amount;
-        coinflip.won=0;
         }
         else
         {
-            msg!("You haven't won or you have already claimed");
+            if owner_token_account.data_is_empty()
+            {
+                invoke(
+                    &spl_associated_token_account::instruction::create_associated_token_account(
+                        payer.key,
+                        nft_owner.key,
+                        spl_token_mint.key,
+                        token_program_id.key,
+                    ),&[
+                        payer.clone(),
+                        owner_token_account.clone(),
+                        nft_owner.clone(),
+                        spl_token_mint.clone(),
+                        token_program_id.clone(),
+                        rent_info.clone(),
+                        associated_token_info.clone(),
+                        system_program.clone()
+                    ]
+                )?;
+            }
+            transfer_tokens(
+                token_program_id,
+                spl_vault_associated_address,
+                spl_token_mint,
+                owner_token_account,
+                nft_vault,
+                accounts,
+                unsold,
+                &[nft_vault_signer_seeds],
+            )?;
+        }
+        escrow.settle_unsold()?;
+        escrow.save(pda)?;
+        msg!("Settled {} unsold fractions, {} in circulation",unsold,escrow.number_of_tokens);
+        Ok(())
+    }
+    pub fn process_sync_holder(program_id: &Pubkey,accounts: &[AccountInfo],)-> ProgramResult {
+        //creating or refreshing a holder record, mints and burns don't run the transfer hook
+        let SyncHolderAccounts{
+            payer,
+            holder_record,
+            owner,
+            token_account,
+            mint,
+            system_program,
+            holder_record_bump,
+        } = SyncHolderAccounts::validate(program_id, accounts)?;
+
+        let balance = if token_account.data_is_empty() {
+            0
+        } else {
+            get_token_balance(token_account)?
+        };
+        let mut record = if holder_record.data_is_empty() {
+            let holder_signer_seeds: &[&[_]] = &[
+                HOLDERPREFIX.as_bytes(),
+                &mint.key.to_bytes(),
+                &owner.key.to_bytes(),
+                &[holder_record_bump],
+            ];
+            let rent = Rent::get()?;
+            create_account_signed(
+                payer,
+                rent.minimum_balance(HolderRecord::LEN),
+                HolderRecord::LEN,
+                program_id,
+                system_program,
+                holder_record,
+                holder_signer_seeds,
+            )?;
+            assert_rent_exempt(holder_record, &rent)?;
+            HolderRecord{
+                account_type: AccountType::HolderRecord,
+                version: HolderRecord::VERSION,
+                mint: *mint.key,
+                owner: *owner.key,
+                token_account: *token_account.key,
+                balance: 0,
+                first_acquired_at: 0,
+                count: 0,
+                checkpoints: [Checkpoint::default(); MAX_CHECKPOINTS],
+            }
+        } else {
+            HolderRecord::load(holder_record, program_id)?
+        };
+        if record.count == 0 || record.balance != balance
+        {
+            record.record_balance(current_timestamp()?, balance);
         }
+        record.save(holder_record)?;
+        msg!("Holder {} holds {}",owner.key,balance);
+        Ok(())
+    }
+    pub fn process_transfer_hook_execute(program_id: &Pubkey,accounts: &[AccountInfo],amount: u64)-> ProgramResult {
+        //updating the holder records of both sides after a transfer
+        let TransferHookExecuteAccounts{
+            source,
+            destination,
+            source_record,
+            destination_record,
+            ..
+        } = TransferHookExecuteAccounts::validate(program_id, accounts, amount)?;
 
-        coinflip.serialize(&mut &mut coinflip_pda.data.borrow_mut()[..])?;
-        pda_check.serialize(&mut &mut pda.data.borrow_mut()[..])?;
+        let now = current_timestamp()?;
+        for (token_account, record_info) in [(source, source_record), (destination, destination_record)] {
+            //wallets opt in with SyncHolder, transfers without a record are not tracked
+            if record_info.owner != program_id || record_info.data_is_empty()
+            {
+                continue;
+            }
+            let mut record = HolderRecord::load(record_info, program_id)?;
+            if record.token_account != *token_account.key
+            {
+                continue;
+            }
+            record.record_balance(now, get_token_balance(token_account)?);
+            record.save(record_info)?;
+        }
+        Ok(())
+    }
+    pub fn process_init_holder_account_metas(program_id: &Pubkey,accounts: &[AccountInfo],extra_account_metas: Vec<ExtraAccountMeta>)-> ProgramResult {
+        //writing the holder records into the validation account of a fraction mint
+        let InitHolderAccountMetasAccounts{
+            extra_account_metas: validation_info,
+            mint,
+            system_program,
+            extra_account_metas_bump,
+            ..
+        } = InitHolderAccountMetasAccounts::validate(program_id, accounts)?;
 
+        let metas = holder_account_metas()?;
+        if extra_account_metas != metas
+        {
+            msg!("Only the holder records can be resolved by the hook");
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        let size = ExtraAccountMetaList::size_of(metas.len())?;
+        let bump = [extra_account_metas_bump];
+        let signer_seeds = collect_extra_account_metas_signer_seeds(mint.key, &bump);
+        invoke_signed(
+            &system_instruction::allocate(validation_info.key, size as u64),
+            &[validation_info.clone(), system_program.clone()],
+            &[&signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(validation_info.key, program_id),
+            &[validation_info.clone(), system_program.clone()],
+            &[&signer_seeds],
+        )?;
+        assert_rent_exempt(validation_info, &Rent::get()?)?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut validation_info.try_borrow_mut_data()?, &metas)?;
         Ok(())
     }
     pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
         let instruction = TokenInstruction::unpack(input)?;
         match instruction {
-            TokenInstruction::ProcessDeposit (ProcessDeposit{
-                number_of_tokens,
-                price,
-            }) => {
+            TokenInstruction::ProcessDeposit(deposit) => {
                 msg!("Instruction: Fractionalizing NFT");
-                Self::process_deposit_nft(program_id,accounts,number_of_tokens, price)
+                Self::process_deposit_nft(program_id,accounts,deposit)
             }
-            TokenInstruction::ProcessBuy(ProcessBuy{token,price}) => {
+            TokenInstruction::ProcessBuy(ProcessBuy{token,price,allowance}) => {
                 msg!("Instruction: Buy token");
-                Self::process_buy_nft_token(program_id,accounts,token,price)
+                Self::process_buy_nft_token(program_id,accounts,token,price,allowance)
             }
             TokenInstruction::ProcessBuy2(ProcessBuy2{day}) => {
                 msg!("Instruction:  Buy token");
@@ -1111,8 +2789,211 @@ impl Processor {
                 msg!("Instruction:  Auction");
                 Self::auction1(program_id, accounts, price)
             }
+            TokenInstruction::MigrateVault => {
+                msg!("Instruction:  Migrate Vault");
+                Self::process_migrate_vault(program_id,accounts)
+            }
+            TokenInstruction::InitConfig(InitConfig{fee_bps,treasury}) => {
+                msg!("Instruction:  Init Config");
+                Self::process_init_config(program_id,accounts,fee_bps,treasury)
+            }
+            TokenInstruction::UpdateConfig(UpdateConfig{fee_bps,treasury,authority}) => {
+                msg!("Instruction:  Update Config");
+                Self::process_update_config(program_id,accounts,fee_bps,treasury,authority)
+            }
+            TokenInstruction::WithdrawProceeds => {
+                msg!("Instruction:  Withdraw Proceeds");
+                Self::process_withdraw_proceeds(program_id,accounts)
+            }
+            TokenInstruction::InitStakePool => {
+                msg!("Instruction:  Init Stake Pool");
+                Self::process_init_stake_pool(program_id,accounts)
+            }
+            TokenInstruction::Stake(Stake{amount}) => {
+                msg!("Instruction:  Stake");
+                Self::process_stake(program_id,accounts,amount)
+            }
+            TokenInstruction::Unstake(Unstake{amount}) => {
+                msg!("Instruction:  Unstake");
+                Self::process_unstake(program_id,accounts,amount)
+            }
+            TokenInstruction::ClaimStakeRewards => {
+                msg!("Instruction:  Claim Stake Rewards");
+                Self::process_claim_stake_rewards(program_id,accounts)
+            }
+            TokenInstruction::CreateProposal(CreateProposal{reserve_price,auction_interval,inflation_bps}) => {
+                msg!("Instruction:  Create Proposal");
+                Self::process_create_proposal(program_id,accounts,reserve_price,auction_interval,inflation_bps)
+            }
+            TokenInstruction::CastVote(CastVote{amount,support}) => {
+                msg!("Instruction:  Cast Vote");
+                Self::process_cast_vote(program_id,accounts,amount,support)
+            }
+            TokenInstruction::ExecuteProposal => {
+                msg!("Instruction:  Execute Proposal");
+                Self::process_execute_proposal(program_id,accounts)
+            }
+            TokenInstruction::WithdrawVote => {
+                msg!("Instruction:  Withdraw Vote");
+                Self::process_withdraw_vote(program_id,accounts)
+            }
+            TokenInstruction::CreateBuyout(CreateBuyout{price}) => {
+                msg!("Instruction:  Create Buyout");
+                Self::process_create_buyout(program_id,accounts,price)
+            }
+            TokenInstruction::CastBuyoutVote(CastBuyoutVote{amount}) => {
+                msg!("Instruction:  Cast Buyout Vote");
+                Self::process_cast_buyout_vote(program_id,accounts,amount)
+            }
+            TokenInstruction::ExecuteBuyout => {
+                msg!("Instruction:  Execute Buyout");
+                Self::process_execute_buyout(program_id,accounts)
+            }
+            TokenInstruction::RefundBuyout => {
+                msg!("Instruction:  Refund Buyout");
+                Self::process_refund_buyout(program_id,accounts)
+            }
+            TokenInstruction::RedeemBuyout(RedeemBuyout{amount}) => {
+                msg!("Instruction:  Redeem Buyout");
+                Self::process_redeem_buyout(program_id,accounts,amount)
+            }
+            TokenInstruction::ClaimSaleRefund => {
+                msg!("Instruction:  Claim Sale Refund");
+                Self::process_claim_sale_refund(program_id,accounts)
+            }
+            TokenInstruction::SellToCurve(SellToCurve{amount,min_payout}) => {
+                msg!("Instruction:  Sell To Curve");
+                Self::process_sell_to_curve(program_id,accounts,amount,min_payout)
+            }
+            TokenInstruction::QuoteSale(QuoteSale{amount,sell}) => {
+                msg!("Instruction:  Quote Sale");
+                Self::process_quote_sale(program_id,accounts,amount,sell)
+            }
+            TokenInstruction::WithdrawSaleReserve => {
+                msg!("Instruction:  Withdraw Sale Reserve");
+                Self::process_withdraw_sale_reserve(program_id,accounts)
+            }
+            TokenInstruction::RefundCrowdfund(RefundCrowdfund{amount}) => {
+                msg!("Instruction:  Refund Crowdfund");
+                Self::process_refund_crowdfund(program_id,accounts,amount)
+            }
+            TokenInstruction::ReclaimCrowdfundNft => {
+                msg!("Instruction:  Reclaim Crowdfund Nft");
+                Self::process_reclaim_crowdfund_nft(program_id,accounts)
+            }
+            TokenInstruction::SettleUnsold => {
+                msg!("Instruction:  Settle Unsold");
+                Self::process_settle_unsold(program_id,accounts)
+            }
+            TokenInstruction::SyncHolder => {
+                msg!("Instruction:  Sync Holder");
+                Self::process_sync_holder(program_id,accounts)
+            }
+            TokenInstruction::TransferHookExecute(TransferHookExecute{amount}) => {
+                msg!("Instruction:  Transfer Hook Execute");
+                Self::process_transfer_hook_execute(program_id,accounts,amount)
+            }
+            TokenInstruction::InitHolderAccountMetas(InitHolderAccountMetas{extra_account_metas}) => {
+                msg!("Instruction:  Init Holder Account Metas");
+                Self::process_init_holder_account_metas(program_id,accounts,extra_account_metas)
+            }
+    }
+}
+}
+/// Purchase of the buyer, creating the pda on its first capped buy
+fn open_purchase<'a>(
+    program_id: &Pubkey,
+    buyer: &AccountInfo<'a>,
+    pda: &AccountInfo<'a>,
+    purchase_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    purchase: Option<Purchase>,
+    purchase_bump: u8,
+) -> Result<Purchase, ProgramError> {
+    if let Some(purchase) = purchase {
+        return Ok(purchase);
+    }
+    let purchase_signer_seeds: &[&[_]] = &[
+        PURCHASEPREFIX.as_bytes(),
+        &buyer.key.to_bytes(),
+        &pda.key.to_bytes(),
+        &[purchase_bump],
+    ];
+    let rent = Rent::get()?;
+    create_account_signed(
+        buyer,
+        rent.minimum_balance(Purchase::LEN),
+        Purchase::LEN,
+        program_id,
+        system_program,
+        purchase_info,
+        purchase_signer_seeds,
+    )?;
+    assert_rent_exempt(purchase_info, &rent)?;
+    Ok(Purchase{
+        account_type: AccountType::Purchase,
+        version: Purchase::VERSION,
+        vault: *pda.key,
+        buyer: *buyer.key,
+        bought: 0,
+        premium: 0,
+        paid: 0,
+    })
+}
+/// Adds a paid protocol fee to the vault and config totals
+fn record_protocol_fee(escrow: &mut NftDetails, config: &mut Config, fee: u64) -> ProgramResult {
+    escrow.protocol_fees = escrow.protocol_fees.checked_add(fee).ok_or(TokenError::Overflow)?;
+    config.total_fees = config.total_fees.checked_add(fee).ok_or(TokenError::Overflow)?;
+    Ok(())
+}
+/// Adds paid creator royalties to the vault total
+fn record_royalty(escrow: &mut NftDetails, royalty: u64) -> ProgramResult {
+    escrow.royalties_paid = escrow.royalties_paid.checked_add(royalty).ok_or(TokenError::Overflow)?;
+    Ok(())
+}
+/// Builds the proceeds of a vault, rejecting empty, oversized or duplicate beneficiary lists and zero shares
+fn build_proceeds(vault: &Pubkey, beneficiaries: &[(Pubkey, u16)]) -> Result<Proceeds, ProgramError> {
+    if beneficiaries.is_empty() || beneficiaries.len() > MAX_BENEFICIARIES
+    {
+        return Err(TokenError::InvalidBeneficiaries.into());
+    }
+    let mut proceeds = Proceeds{
+        account_type: AccountType::Proceeds,
+        version: Proceeds::VERSION,
+        vault: *vault,
+        count: 0,
+        beneficiaries: [Beneficiary::default(); MAX_BENEFICIARIES],
+    };
+    for (index, (address, share)) in beneficiaries.iter().enumerate() {
+        if *share == 0 || beneficiaries[..index].iter().any(|(other, _)| other == address)
+        {
+            msg!("Invalid beneficiary {}",address);
+            return Err(TokenError::InvalidBeneficiaries.into());
+        }
+        proceeds.beneficiaries[index] = Beneficiary{
+            address: *address,
+            share: *share,
+            balance: 0,
+        };
     }
+    proceeds.count = beneficiaries.len() as u8;
+    Ok(proceeds)
 }
+/// Pays the nft owner's part of a sale to payee, the proceeds quote account when the vault has beneficiaries
+fn pay_curator<'a>(
+    quote: &Quote<'_, 'a>,
+    source: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    payee: &AccountInfo<'a>,
+    proceeds: &mut Option<Proceeds>,
+    signer_seeds: Option<&[&[u8]]>,
+    amount: u64,
+) -> ProgramResult {
+    quote.transfer(source, authority, payee, amount, signer_seeds)?;
+    if let Some(proceeds) = proceeds {
+        proceeds.credit(amount)?;
+    }
+    Ok(())
 }
 impl PrintProgramError for TokenError {
     fn print<E>(&self)
@@ -1128,6 +3009,108 @@ impl PrintProgramError for TokenError {
             TokenError::Notstarted =>msg!("Error: Not started"),
             TokenError::TokenFinished =>msg!("Error: Token Finished"),
             TokenError::PriceLower =>msg!("Error: Price is Lower"),
+            TokenError::InvalidAccountType =>msg!("Error: Account type mismatch"),
+            TokenError::InvalidAccountVersion =>msg!("Error: Account version mismatch"),
+            TokenError::InvalidTokenProgram =>msg!("Error: Invalid token program"),
+            TokenError::InvalidAssociatedTokenProgram =>msg!("Error: Invalid associated token program"),
+            TokenError::InvalidSystemProgram =>msg!("Error: Invalid system program"),
+            TokenError::InvalidRentSysvar =>msg!("Error: Invalid rent sysvar"),
+            TokenError::InvalidVault =>msg!("Error: Invalid vault"),
+            TokenError::InvalidMint =>msg!("Error: Invalid mint"),
+            TokenError::InvalidTokenAccount =>msg!("Error: Invalid token account"),
+            TokenError::InvalidNftOwner =>msg!("Error: Nft owner doesn't match the vault"),
+            TokenError::InvalidAuction =>msg!("Error: Invalid auction account"),
+            TokenError::InvalidPreviousBidder =>msg!("Error: Previous highest bidder doesn't match"),
+            TokenError::AccountNotWritable =>msg!("Error: Account is not writable"),
+            TokenError::InsufficientStake =>msg!("Error: Insufficient stake"),
+            TokenError::WrongAuctionDay =>msg!("Error: Wrong auction day"),
+            TokenError::AlreadyClaimed =>msg!("Error: Already claimed"),
+            TokenError::NotAuctionWinner =>msg!("Error: Not the auction winner"),
+            TokenError::NotCoinFlipWinner =>msg!("Error: Not the coin flip winner"),
+            TokenError::InvalidClock =>msg!("Error: Invalid clock"),
+            TokenError::InvalidConfig =>msg!("Error: Invalid config"),
+            TokenError::InvalidTreasury =>msg!("Error: Invalid treasury"),
+            TokenError::InvalidFee =>msg!("Error: Fee above 100%"),
+            TokenError::InvalidAuthority =>msg!("Error: Invalid authority"),
+            TokenError::InvalidMetadata =>msg!("Error: Invalid metadata"),
+            TokenError::InvalidCreator =>msg!("Error: Invalid creator"),
+            TokenError::InvalidBeneficiaries =>msg!("Error: Invalid beneficiaries"),
+            TokenError::InvalidProceeds =>msg!("Error: Invalid proceeds account"),
+            TokenError::NotBeneficiary =>msg!("Error: Not a beneficiary of the vault"),
+            TokenError::InvalidStakePool =>msg!("Error: Invalid stake pool"),
+            TokenError::InvalidStakeEntry =>msg!("Error: Invalid stake entry"),
+            TokenError::InvalidProposal =>msg!("Error: Invalid proposal"),
+            TokenError::VotingClosed =>msg!("Error: Voting is closed"),
+            TokenError::TimelockActive =>msg!("Error: Proposal is not executable yet"),
+            TokenError::ProposalNotPassed =>msg!("Error: Proposal didn't pass"),
+            TokenError::ProposalExecuted =>msg!("Error: Proposal already executed"),
+            TokenError::InvalidVote =>msg!("Error: Invalid vote"),
+            TokenError::InvalidBuyout =>msg!("Error: Invalid buyout"),
+            TokenError::BuyoutBelowValuation =>msg!("Error: Buyout below the implied valuation"),
+            TokenError::BuyoutClosed =>msg!("Error: Buyout is closed"),
+            TokenError::BuyoutOpen =>msg!("Error: Buyout is still open"),
+            TokenError::VaultBoughtOut =>msg!("Error: Vault was bought out"),
+            TokenError::InvalidTransferHook =>msg!("Error: Invalid transfer hook"),
+            TokenError::InvalidHolderRecord =>msg!("Error: Invalid holder record"),
+            TokenError::HoldingPeriodNotMet =>msg!("Error: Holding period not met"),
+            TokenError::InvalidPurchase =>msg!("Error: Invalid purchase"),
+            TokenError::NotAllowlisted =>msg!("Error: Buyer is not allowlisted"),
+            TokenError::AllowanceExceeded =>msg!("Error: Allowlist cap exceeded"),
+            TokenError::WalletCapExceeded =>msg!("Error: Wallet cap exceeded"),
+            TokenError::InvalidSaleMode =>msg!("Error: Invalid sale mode"),
+            TokenError::SaleOpen =>msg!("Error: Sale is still open"),
+            TokenError::RaiseFailed =>msg!("Error: Minimum raise not reached"),
+            TokenError::RaiseReached =>msg!("Error: Minimum raise reached"),
+            TokenError::ClaimsPending =>msg!("Error: Auctions or coin flips are still to be claimed"),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::LegacyNftDetails,
+        test_utils::{account_infos, install_stubs, TestAccount},
+    };
+    use borsh::BorshSerialize;
+    use solana_program::system_program;
+
+    #[test]
+    fn migrate_vault_reallocs_and_tops_up_rent() {
+        install_stubs();
+        let program_id = Pubkey::new_unique();
+        let legacy = LegacyNftDetails{
+            number_of_tokens: 1_000,
+            price: 5,
+            nft_mint: Pubkey::new_unique(),
+            nft_escrow: Pubkey::new_unique(),
+            nft_owner: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            create_at: 1_700_000_000,
+            days: 2.0,
+            remaining_token: 400,
+        };
+        let rent = Rent::default();
+        let legacy_rent = rent.minimum_balance(LegacyNftDetails::LEN);
+        let accounts = account_infos(&[
+            TestAccount::new(Pubkey::new_unique(), system_program::id(), 1_000_000_000, vec![]).signer(),
+            TestAccount::new(Pubkey::new_unique(), program_id, legacy_rent, legacy.try_to_vec().unwrap()),
+            TestAccount::new(system_program::id(), Pubkey::default(), 1, vec![]).readonly(),
+        ]);
+
+        Processor::process_migrate_vault(&program_id, &accounts).unwrap();
+
+        let top_up = rent.minimum_balance(NftDetails::LEN) - legacy_rent;
+        assert_eq!(accounts[0].lamports(), 1_000_000_000 - top_up);
+        assert_eq!(accounts[1].lamports(), rent.minimum_balance(NftDetails::LEN));
+        assert_eq!(accounts[1].data_len(), NftDetails::LEN);
+        let escrow = NftDetails::load(&accounts[1], &program_id).unwrap();
+        assert_eq!(escrow, NftDetails::from(legacy));
+
+        assert_eq!(
+            Processor::process_migrate_vault(&program_id, &accounts),
+            Err(TokenError::InvalidAccountVersion.into())
+        );
+    }
+}
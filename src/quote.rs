@@ -0,0 +1,116 @@
+//! Payments in the quote currency of a vault, native SOL or an SPL token
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke,invoke_signed},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent,Sysvar},
+    msg,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use crate::{
+    error::TokenError,
+    utils::{
+        assert_writable,
+        assert_withdrawal_keeps_rent,
+        transfer_lamports,
+        transfer_lamports_signed,
+        transfer_program_lamports,
+    },
+};
+
+/// Quote currency of a vault, a default mint means native SOL.
+///
+/// Every party is paid through its quote account: the wallet itself for SOL,
+/// or the associated token account of the wallet for an SPL quote mint.
+pub struct Quote<'a, 'b: 'a> {
+    pub mint: Pubkey,
+    pub token_program_id: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+impl<'a, 'b: 'a> Quote<'a, 'b> {
+    pub fn new(mint: Pubkey, token_program_id: &'a AccountInfo<'b>, system_program: &'a AccountInfo<'b>) -> Self {
+        Self{
+            mint,
+            token_program_id,
+            system_program,
+        }
+    }
+    pub fn is_native(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+    /// Address that holds the quote currency of wallet
+    pub fn account_of(&self, wallet: &Pubkey) -> Pubkey {
+        if self.is_native() {
+            *wallet
+        } else {
+            get_associated_token_address_with_program_id(wallet, &self.mint, self.token_program_id.key)
+        }
+    }
+    /// Checks account is the writable quote account of wallet
+    pub fn assert_account(&self, account: &AccountInfo, wallet: &Pubkey) -> ProgramResult {
+        if *account.key != self.account_of(wallet)
+        {
+            msg!("{} is not the quote account of {}", account.key, wallet);
+            return Err(TokenError::InvalidTokenAccount.into());
+        }
+        assert_writable(account)
+    }
+    /// Pays amount out of the quote account from owned by authority, zero amounts are skipped.
+    /// For SOL from is the authority itself, signer_seeds are required when it is a pda
+    pub fn transfer(
+        &self,
+        from: &AccountInfo<'b>,
+        authority: &AccountInfo<'b>,
+        to: &AccountInfo<'b>,
+        amount: u64,
+        signer_seeds: Option<&[&[u8]]>,
+    ) -> ProgramResult {
+        if amount == 0 {
+            return Ok(());
+        }
+        if self.is_native() {
+            return match signer_seeds {
+                Some(seeds) => {
+                    assert_withdrawal_keeps_rent(from, amount, &Rent::get()?)?;
+                    transfer_lamports_signed(from, to, amount, self.system_program, seeds)
+                }
+                None => transfer_lamports(from, to, amount, self.system_program),
+            };
+        }
+        // Token-2022 shares the spl_token transfer layout, which it accepts for quote mints without a transfer fee
+        let mut instruction = spl_token::instruction::transfer(
+            &spl_token::id(),
+            from.key,
+            to.key,
+            authority.key,
+            &[authority.key],
+            amount,
+        )?;
+        instruction.program_id = *self.token_program_id.key;
+        let account_infos = [
+            self.token_program_id.clone(),
+            from.clone(),
+            to.clone(),
+            authority.clone(),
+        ];
+        match signer_seeds {
+            Some(seeds) => invoke_signed(&instruction, &account_infos, &[seeds]),
+            None => invoke(&instruction, &account_infos),
+        }
+    }
+    /// Pays amount out of the quote account of a program owned pda, which keeps its rent for SOL
+    pub fn withdraw(
+        &self,
+        from: &AccountInfo<'b>,
+        authority: &AccountInfo<'b>,
+        to: &AccountInfo<'b>,
+        amount: u64,
+        signer_seeds: &[&[u8]],
+    ) -> ProgramResult {
+        if self.is_native() {
+            return transfer_program_lamports(from, to, amount, &Rent::get()?);
+        }
+        self.transfer(from, authority, to, amount, Some(signer_seeds))
+    }
+}
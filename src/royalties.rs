@@ -0,0 +1,204 @@
+//! Metaplex creator royalties
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg,
+};
+use crate::{
+    error::TokenError,
+    quote::Quote,
+    METADATAPREFIX,
+};
+use std::convert::TryFrom;
+
+/// Metaplex token metadata program
+pub const METADATA_PROGRAM_ID: Pubkey = solana_program::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+/// Key of a MetadataV1 account
+const METADATA_V1_KEY: u8 = 4;
+
+#[derive(BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    /// Percentage of the royalty owed to the creator
+    pub share: u8,
+}
+
+/// Leading fields of a Metaplex metadata account, up to the creators
+#[derive(BorshDeserialize)]
+struct MetadataHeader {
+    key: u8,
+    _update_authority: Pubkey,
+    mint: Pubkey,
+    _name: String,
+    _symbol: String,
+    _uri: String,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<Creator>>,
+}
+
+/// Royalty configuration read from the metadata of the deposited nft
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Royalties {
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<Creator>,
+}
+impl Royalties {
+    /// Reads the royalties of mint, an nft without metadata pays no royalties
+    pub fn load(metadata: &AccountInfo, mint: &Pubkey) -> Result<Self, ProgramError> {
+        let (metadata_address, _bump) = Pubkey::find_program_address(
+            &[
+                METADATAPREFIX.as_bytes(),
+                METADATA_PROGRAM_ID.as_ref(),
+                mint.as_ref(),
+            ],
+            &METADATA_PROGRAM_ID,
+        );
+        if metadata_address != *metadata.key
+        {
+            msg!("{} is not the metadata of {}", metadata.key, mint);
+            return Err(TokenError::InvalidMetadata.into());
+        }
+        if metadata.data_is_empty()
+        {
+            return Ok(Self::default());
+        }
+        if *metadata.owner != METADATA_PROGRAM_ID
+        {
+            return Err(TokenError::InvalidMetadata.into());
+        }
+        let data = metadata.try_borrow_data()?;
+        let header = MetadataHeader::deserialize(&mut &data[..])
+            .map_err(|_| TokenError::InvalidMetadata)?;
+        if header.key != METADATA_V1_KEY || header.mint != *mint
+        {
+            return Err(TokenError::InvalidMetadata.into());
+        }
+        Ok(Self{
+            seller_fee_basis_points: header.seller_fee_basis_points,
+            creators: header.creators.unwrap_or_default(),
+        })
+    }
+    /// Splits the royalty owed on amount between the verified creators by share,
+    /// rounding dust goes to the last verified creator
+    pub fn split(&self, amount: u64) -> Result<Vec<(Pubkey, u64)>, ProgramError> {
+        let verified: Vec<&Creator> = self.creators.iter().filter(|creator| creator.verified).collect();
+        let total_shares: u128 = verified.iter().map(|creator| creator.share as u128).sum();
+        if total_shares == 0
+        {
+            return Ok(vec![]);
+        }
+        let royalty = amount as u128 * self.seller_fee_basis_points.min(10000) as u128 / 10000;
+        let mut remaining = royalty;
+        let mut payouts = Vec::with_capacity(verified.len());
+        for (index, creator) in verified.iter().enumerate() {
+            let payout = if index + 1 == verified.len() {
+                remaining
+            } else {
+                royalty * creator.share as u128 / total_shares
+            };
+            remaining -= payout;
+            let payout = u64::try_from(payout).map_err(|_| TokenError::Overflow)?;
+            payouts.push((creator.address, payout));
+        }
+        Ok(payouts)
+    }
+}
+
+/// Finds the writable quote account of a creator among the trailing creator accounts
+pub fn find_creator<'a, 'b>(creators: &'a [AccountInfo<'b>], address: &Pubkey, quote: &Quote) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let quote_account = quote.account_of(address);
+    let creator = creators
+        .iter()
+        .find(|creator| *creator.key == quote_account)
+        .ok_or_else(|| {
+            msg!("Creator {} is missing", address);
+            ProgramError::from(TokenError::InvalidCreator)
+        })?;
+    if !creator.is_writable
+    {
+        return Err(TokenError::AccountNotWritable.into());
+    }
+    Ok(creator)
+}
+
+/// Pays the creator royalties owed on amount out of the source quote account and returns the total paid,
+/// signer_seeds are required when the authority of source is a pda
+pub fn pay_royalties<'a>(
+    quote: &Quote<'_, 'a>,
+    source: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    creators: &[AccountInfo<'a>],
+    signer_seeds: Option<&[&[u8]]>,
+    royalties: &Royalties,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let mut paid: u64 = 0;
+    for (address, payout) in royalties.split(amount)? {
+        if payout == 0
+        {
+            continue;
+        }
+        let creator = find_creator(creators, &address, quote)?;
+        quote.transfer(source, authority, creator, payout, signer_seeds)?;
+        paid = paid.checked_add(payout).ok_or(TokenError::Overflow)?;
+    }
+    msg!("Royalties paid {}", paid);
+    Ok(paid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creator(share: u8, verified: bool) -> Creator {
+        Creator{
+            address: Pubkey::new_unique(),
+            verified,
+            share,
+        }
+    }
+
+    #[test]
+    fn split_pays_verified_creators_by_share() {
+        let creators = vec![creator(30, true), creator(20, false), creator(50, true)];
+        let royalties = Royalties{
+            seller_fee_basis_points: 500,
+            creators: creators.clone(),
+        };
+        // 5% of 10_000 between shares of 30 and 50
+        assert_eq!(
+            royalties.split(10_000).unwrap(),
+            vec![(creators[0].address, 187), (creators[2].address, 313)]
+        );
+    }
+
+    #[test]
+    fn split_hands_the_rounding_dust_to_the_last_creator() {
+        let creators = vec![creator(1, true), creator(1, true), creator(1, true)];
+        let royalties = Royalties{
+            seller_fee_basis_points: 10000,
+            creators: creators.clone(),
+        };
+        let payouts = royalties.split(100).unwrap();
+        assert_eq!(payouts.iter().map(|(_, payout)| payout).collect::<Vec<_>>(), vec![&33, &33, &34]);
+        assert_eq!(payouts.iter().map(|(_, payout)| payout).sum::<u64>(), 100);
+    }
+
+    #[test]
+    fn split_caps_the_fee_and_skips_unverified_creators() {
+        let royalties = Royalties{
+            seller_fee_basis_points: 20000,
+            creators: vec![creator(100, true)],
+        };
+        assert_eq!(royalties.split(u64::MAX).unwrap()[0].1, u64::MAX);
+        let royalties = Royalties{
+            seller_fee_basis_points: 500,
+            creators: vec![creator(100, false)],
+        };
+        assert_eq!(royalties.split(10_000).unwrap(), vec![]);
+        assert_eq!(Royalties::default().split(10_000).unwrap(), vec![]);
+    }
+}
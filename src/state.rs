@@ -1,14 +1,115 @@
 //! State transition types
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::{rent::Rent,Sysvar},
 };
+use crate::{
+    error::TokenError,
+    utils::{assert_rent_exempt,seconds_since},
+};
+use std::convert::TryFrom;
+
+/// Discriminator stored in the first byte of every program owned account
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum AccountType {
+    Uninitialized,
+    NftDetails,
+    CoinFlip,
+    Auction,
+    Config,
+    Proceeds,
+    StakePool,
+    StakeEntry,
+    Proposal,
+    Vote,
+    Buyout,
+    HolderRecord,
+    Purchase,
+}
 
+/// Type and layout checks shared by every program owned account
+pub trait ProgramAccount: BorshSerialize + BorshDeserialize {
+    /// Discriminator written in the first byte of the account
+    const ACCOUNT_TYPE: AccountType;
+    /// Layout version written in the second byte of the account
+    const VERSION: u8;
+    /// Borsh serialized length of the account
+    const LEN: usize;
+
+    /// Deserializes the account after checking its owner, size, type and version
+    fn load(account: &AccountInfo, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        if account.owner != program_id
+        {
+            return Err(ProgramError::IllegalOwner);
+        }
+        let data = account.try_borrow_data()?;
+        if data.len() != Self::LEN || data[0] != Self::ACCOUNT_TYPE as u8
+        {
+            return Err(TokenError::InvalidAccountType.into());
+        }
+        if data[1] != Self::VERSION
+        {
+            return Err(TokenError::InvalidAccountVersion.into());
+        }
+        assert_rent_exempt(account, &Rent::get()?)?;
+        Self::try_from_slice(&data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+    /// Serializes the account back into its data
+    fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+/// Pricing of the primary sale of a vault
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum SaleMode {
+    /// Every fraction sells at price
+    Fixed,
+    /// The price decays linearly from start_price to price over the buy period
+    Dutch,
+    /// The price starts at price and rises by curve_param every CURVE_UNIT sold
+    LinearCurve,
+    /// The price starts at price and grows by curve_param basis points every CURVE_UNIT sold
+    ExponentialCurve,
+}
+
+/// Fractions in one step of a bonding curve, one whole fraction of the 9 decimals mint
+pub const CURVE_UNIT: u64 = 1_000_000_000;
+/// Fixed point scale of the exponential curve growth
+pub const CURVE_PRECISION: u128 = 1_000_000_000_000;
+
+/// Fixed point base raised to exponent, both scaled by CURVE_PRECISION
+fn curve_pow(base: u128, mut exponent: u128) -> Result<u128, ProgramError> {
+    let mut result = CURVE_PRECISION;
+    let mut base = base;
+    while exponent > 0 {
+        if exponent & 1 == 1
+        {
+            result = result.checked_mul(base).ok_or(TokenError::Overflow)? / CURVE_PRECISION;
+        }
+        exponent >>= 1;
+        if exponent > 0
+        {
+            base = base.checked_mul(base).ok_or(TokenError::Overflow)? / CURVE_PRECISION;
+        }
+    }
+    Ok(result)
+}
 
 /// Initializeing solana stream states
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct NftDetails{
+    pub account_type: AccountType,
+    pub version: u8,
     pub number_of_tokens: u64,
     pub price: u64,
     pub nft_mint: Pubkey,
@@ -16,22 +117,1573 @@ pub struct NftDetails{
     pub nft_owner:Pubkey,
     pub token_mint: Pubkey,
     pub create_at: u64,
-    pub days: f64,
     pub remaining_token:u64,
+    /// Day index of the most recently opened auction, 0 before the first auction
+    pub current_day: u64,
+    /// Day index of the most recently settled auction
+    pub last_settled_day: u64,
+    /// Number of auctions opened for the vault
+    pub total_auctions: u64,
+    /// Lamports paid to the protocol treasury from this vault's sales
+    pub protocol_fees: u64,
+    /// Lamports paid to the nft creators from this vault's sales
+    pub royalties_paid: u64,
+    /// Sale proceeds accrue to the beneficiaries of the proceeds account instead of the nft owner
+    pub split_proceeds: bool,
+    /// Lowest accepted auction bid
+    pub reserve_price: u64,
+    /// Length of the buy period and of every auction in seconds
+    pub auction_interval: u64,
+    /// Fractions minted for every auction, in basis points of the supply
+    pub inflation_bps: u16,
+    /// Start of the current auction interval, moved when the interval is changed
+    pub period_start: u64,
+    /// Day index at period_start
+    pub period_offset: u64,
+    /// Winning bid of the most recently settled auction
+    pub last_auction_price: u64,
+    /// Fractions sold in the most recently settled auction
+    pub last_auction_tokens: u64,
+    /// The nft was sold through a buyout, fractions can only be redeemed
+    pub bought_out: bool,
+    /// Time weighted average clearing price of one fraction, scaled by PRICE_SCALE
+    pub twap_price: u128,
+    /// Time of the last clearing price folded into twap_price, 0 before the first settlement
+    pub twap_updated_at: u64,
+    /// Implied nft valuation at the last settlement, twap_price times the supply
+    pub valuation: u64,
+    /// Mint prices and payments are made in, the default pubkey for native SOL
+    pub quote_mint: Pubkey,
+    /// Token program of the fraction mint, spl_token or spl_token_2022
+    pub token_program: Pubkey,
+    /// Token program of the nft mint
+    pub nft_token_program: Pubkey,
+    /// Seconds a player has to hold the fractions before a coin flip, 0 disables the check
+    pub min_holding_period: u64,
+    /// Merkle root of the buyers allowed in the primary sale and their caps, zero for an open sale
+    pub allowlist_root: [u8; 32],
+    /// Fractions a single wallet can buy in the primary sale, 0 for no limit
+    pub max_per_wallet: u64,
+    /// Pricing of the primary sale
+    pub sale_mode: SaleMode,
+    /// Price per fraction when a dutch sale opens, it decays to price over the buy period
+    pub start_price: u64,
+    /// Dutch sale buyers are refunded down to the clearing price, the premium above price is escrowed until the sale ends
+    pub sale_refunds: bool,
+    /// Price per fraction of the latest dutch sale purchase
+    pub clearing_price: u64,
+    /// Slope or growth in basis points of a bonding curve sale
+    pub curve_param: u64,
+    /// Quote paid into a bonding curve or a crowdfund, escrowed in the vault until the sale ends
+    pub sale_reserve: u64,
+    /// Crowdfund target of the primary sale, 0 pays the curator on every purchase
+    pub min_raise: u64,
+    /// Quote raised by the primary sale toward min_raise
+    pub raised: u64,
+    /// Unsold fractions are burned instead of handed to the curator after the sale
+    pub burn_unsold: bool,
+    /// Auctions opened with a bid that were not settled yet
+    pub open_auctions: u64,
+    /// Coin flips won that were not claimed yet
+    pub pending_flips: u64,
+    /// Token program of the quote mint, spl_token or spl_token_2022
+    pub quote_token_program: Pubkey,
+}
+impl ProgramAccount for NftDetails {
+    const ACCOUNT_TYPE: AccountType = AccountType::NftDetails;
+    /// Only the legacy layout was deployed before it, a later layout change bumps it and teaches upgrade the replaced one
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 8 + 8 + 32 * 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 1 + 16 + 8 + 8 + 32 + 32 + 32 + 8 + 32 + 8 + 1 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 32;
+}
+
+/// Default auction interval of one day
+pub const DEFAULT_AUCTION_INTERVAL: u64 = 86400;
+/// Default auction inflation of 1% of the supply
+pub const DEFAULT_INFLATION_BPS: u16 = 100;
+/// Fixed point scale of the per fraction prices
+pub const PRICE_SCALE: u128 = 1_000_000_000_000;
+/// Window of the clearing price average, older prices fade out over it
+pub const TWAP_WINDOW: u64 = 7 * 86400;
+
+impl NftDetails {
+    /// Day index at now, day 0 is the buy period and every later day runs one auction
+    pub fn day_at(&self, now: u64) -> Result<u64, ProgramError> {
+        let elapsed = seconds_since(now, self.period_start)?;
+        self.period_offset
+            .checked_add(elapsed / self.auction_interval)
+            .ok_or_else(|| TokenError::Overflow.into())
+    }
+    /// Changes the auction interval, the running day ends now so day indexes never repeat
+    pub fn set_auction_interval(&mut self, now: u64, auction_interval: u64) -> ProgramResult {
+        self.period_offset = self.day_at(now)?.checked_add(1).ok_or(TokenError::Overflow)?;
+        self.period_start = now;
+        self.auction_interval = auction_interval;
+        Ok(())
+    }
+    /// Primary sale price per fraction at now, the price of the next fraction on a bonding curve
+    pub fn sale_price_at(&self, now: u64) -> Result<u64, ProgramError> {
+        match self.sale_mode {
+            SaleMode::Fixed => Ok(self.price),
+            SaleMode::Dutch => {
+                let elapsed = seconds_since(now, self.create_at)?.min(self.auction_interval);
+                let decay = (self.start_price - self.price) as u128 * elapsed as u128 / self.auction_interval as u128;
+                Ok(self.start_price - decay as u64)
+            }
+            SaleMode::LinearCurve | SaleMode::ExponentialCurve => {
+                let sold = self.curve_sold();
+                let price = self.curve_total(sold.saturating_add(1))?.saturating_sub(self.curve_total(sold)?);
+                u64::try_from(price).map_err(|_| TokenError::Overflow.into())
+            }
+        }
+    }
+    /// Quote paid for token fractions of the primary sale at now
+    pub fn sale_cost(&self, now: u64, token: u64) -> Result<u64, ProgramError> {
+        if self.is_curve()
+        {
+            let sold = self.curve_sold();
+            let end = sold.checked_add(token).ok_or(TokenError::Overflow)?;
+            let cost = self.curve_total(end)?.saturating_sub(self.curve_total(sold)?);
+            return u64::try_from(cost).map_err(|_| TokenError::Overflow.into());
+        }
+        token.checked_mul(self.sale_price_at(now)?).ok_or_else(|| TokenError::Overflow.into())
+    }
+    /// Quote returned by the bonding curve for token fractions sold back, bounded by the reserve
+    pub fn curve_refund(&self, token: u64) -> Result<u64, ProgramError> {
+        let sold = self.curve_sold();
+        let start = sold.checked_sub(token).ok_or(TokenError::TokenFinished)?;
+        let refund = self.curve_total(sold)?.saturating_sub(self.curve_total(start)?);
+        Ok(refund.min(self.sale_reserve as u128) as u64)
+    }
+    pub fn is_curve(&self) -> bool {
+        self.sale_mode == SaleMode::LinearCurve || self.sale_mode == SaleMode::ExponentialCurve
+    }
+    /// Primary sale payments are escrowed in the sale reserve until the sale ends
+    pub fn escrows_sale(&self) -> bool {
+        self.is_curve() || self.min_raise > 0
+    }
+    /// The buy period ended short of the crowdfund target
+    pub fn raise_failed(&self, now: u64) -> Result<bool, ProgramError> {
+        Ok(self.min_raise > 0 && self.raised < self.min_raise && self.day_at(now)? > 0)
+    }
+    /// Clears the unsold sale fractions, burned ones leave the supply while the curator's still circulate
+    pub fn settle_unsold(&mut self) -> ProgramResult {
+        if self.burn_unsold
+        {
+            self.number_of_tokens = self.number_of_tokens.checked_sub(self.remaining_token).ok_or(TokenError::Overflow)?;
+        }
+        self.remaining_token = 0;
+        Ok(())
+    }
+    /// Fractions sold along the bonding curve, the whole supply is minted to the vault before the sale
+    fn curve_sold(&self) -> u64 {
+        self.number_of_tokens.saturating_sub(self.remaining_token)
+    }
+    /// Quote paid for the first sold fractions of the bonding curve
+    fn curve_total(&self, sold: u64) -> Result<u128, ProgramError> {
+        let price = self.price as u128;
+        let sold = sold as u128;
+        let unit = CURVE_UNIT as u128;
+        let total = match self.sale_mode {
+            SaleMode::LinearCurve => {
+                // sum of price + curve_param * k / CURVE_UNIT for every fraction k before sold
+                let steps = sold * sold.saturating_sub(1) / 2;
+                steps
+                    .checked_mul(self.curve_param as u128)
+                    .map(|rise| rise / unit)
+                    .and_then(|rise| rise.checked_add(sold * price))
+            }
+            SaleMode::ExponentialCurve => {
+                // full steps form a geometric series, the partial step is priced at the current step
+                let growth = CURVE_PRECISION * self.curve_param as u128 / 10000;
+                let full_steps = sold / unit;
+                let scale = curve_pow(CURVE_PRECISION + growth, full_steps)?;
+                let series = (unit * price)
+                    .checked_mul(scale - CURVE_PRECISION)
+                    .map(|series| series / growth);
+                let partial = ((sold - full_steps * unit) * price)
+                    .checked_mul(scale)
+                    .map(|partial| partial / CURVE_PRECISION);
+                series.zip(partial).and_then(|(series, partial)| series.checked_add(partial))
+            }
+            _ => Some(sold * price),
+        };
+        total.ok_or_else(|| TokenError::Overflow.into())
+    }
+    /// Fractions of opened auctions not minted yet, number_of_tokens counts them from the first bid on
+    pub fn unsettled_auction_tokens(&self, supply: u64) -> u64 {
+        self.number_of_tokens.saturating_sub(supply)
+    }
+    /// Fractions minted to the winner of an auction
+    pub fn auction_tokens(&self) -> u64 {
+        (self.number_of_tokens as u128 * self.inflation_bps as u128 / 10000) as u64
+    }
+    /// Value of the whole supply at the average clearing price, or at the sale price before any auction
+    pub fn implied_valuation(&self) -> Result<u64, ProgramError> {
+        let valuation = if self.twap_updated_at > 0 {
+            self.twap_price
+                .checked_mul(self.number_of_tokens as u128)
+                .ok_or(TokenError::Overflow)?
+                / PRICE_SCALE
+        } else {
+            self.price as u128 * self.number_of_tokens as u128
+        };
+        u64::try_from(valuation).map_err(|_| TokenError::Overflow.into())
+    }
+    /// Folds the clearing price of a settled auction into the time weighted average,
+    /// the weight of the new price grows with the time since the last update up to TWAP_WINDOW
+    pub fn record_clearing_price(&mut self, now: u64, price: u64, tokens: u64) -> ProgramResult {
+        self.last_auction_price = price;
+        self.last_auction_tokens = tokens;
+        if tokens == 0
+        {
+            return Ok(());
+        }
+        let clearing_price = (price as u128)
+            .checked_mul(PRICE_SCALE)
+            .ok_or(TokenError::Overflow)?
+            / tokens as u128;
+        if self.twap_updated_at == 0
+        {
+            self.twap_price = clearing_price;
+        }
+        else
+        {
+            let elapsed = seconds_since(now, self.twap_updated_at)?.min(TWAP_WINDOW) as u128;
+            let weighted = self.twap_price
+                .checked_mul(TWAP_WINDOW as u128 - elapsed)
+                .and_then(|old| clearing_price.checked_mul(elapsed).and_then(|new| old.checked_add(new)))
+                .ok_or(TokenError::Overflow)?;
+            self.twap_price = weighted / TWAP_WINDOW as u128;
+        }
+        self.twap_updated_at = now;
+        self.valuation = self.implied_valuation()?;
+        Ok(())
+    }
 }
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct CoinFlip{
+    pub account_type: AccountType,
+    pub version: u8,
     pub won: u64,
     pub address: Pubkey,
     pub amount: u64,
 }
+impl ProgramAccount for CoinFlip {
+    const ACCOUNT_TYPE: AccountType = AccountType::CoinFlip;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 8 + 32 + 8;
+}
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct Auction{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Data account of the vault the auction sells fractions of
+    pub vault: Pubkey,
     pub max_price: u64,
     pub max_payer: Pubkey,
     pub num_tokens: u64,
     pub day:u64,
-}
\ No newline at end of file
+}
+impl ProgramAccount for Auction {
+    const ACCOUNT_TYPE: AccountType = AccountType::Auction;
+    const VERSION: u8 = 2;
+    const LEN: usize = 1 + 1 + 32 + 8 + 32 + 8 + 8;
+}
+
+/// Vault layout written before accounts carried a type and version header
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct LegacyNftDetails{
+    pub number_of_tokens: u64,
+    pub price: u64,
+    pub nft_mint: Pubkey,
+    pub nft_escrow: Pubkey,
+    pub nft_owner:Pubkey,
+    pub token_mint: Pubkey,
+    pub create_at: u64,
+    pub days: f64,
+    pub remaining_token:u64,
+}
+impl LegacyNftDetails {
+    pub const LEN: usize = 8 + 8 + 32 * 4 + 8 + 8 + 8;
+}
+impl NftDetails {
+    /// Rebuilds a vault written with the legacy layout, the fields it lacks take their defaults
+    pub fn upgrade(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() == LegacyNftDetails::LEN
+        {
+            return Ok(LegacyNftDetails::try_from_slice(data)?.into());
+        }
+        // versioned vaults were only written with the current layout
+        if data.first() == Some(&(AccountType::NftDetails as u8))
+        {
+            return Err(TokenError::InvalidAccountVersion.into());
+        }
+        Err(TokenError::InvalidAccountType.into())
+    }
+}
+impl From<LegacyNftDetails> for NftDetails {
+    fn from(legacy: LegacyNftDetails) -> Self {
+        NftDetails{
+            account_type: AccountType::NftDetails,
+            version: NftDetails::VERSION,
+            number_of_tokens: legacy.number_of_tokens,
+            price: legacy.price,
+            nft_mint: legacy.nft_mint,
+            nft_escrow: legacy.nft_escrow,
+            nft_owner: legacy.nft_owner,
+            token_mint: legacy.token_mint,
+            create_at: legacy.create_at,
+            remaining_token: legacy.remaining_token,
+            current_day: 0,
+            last_settled_day: 0,
+            total_auctions: 0,
+            protocol_fees: 0,
+            royalties_paid: 0,
+            split_proceeds: false,
+            reserve_price: 0,
+            auction_interval: DEFAULT_AUCTION_INTERVAL,
+            inflation_bps: DEFAULT_INFLATION_BPS,
+            period_start: legacy.create_at,
+            period_offset: 0,
+            last_auction_price: 0,
+            last_auction_tokens: 0,
+            bought_out: false,
+            twap_price: 0,
+            twap_updated_at: 0,
+            valuation: 0,
+            quote_mint: Pubkey::default(),
+            token_program: spl_token::id(),
+            nft_token_program: spl_token::id(),
+            min_holding_period: 0,
+            allowlist_root: [0; 32],
+            max_per_wallet: 0,
+            sale_mode: SaleMode::Fixed,
+            start_price: 0,
+            sale_refunds: false,
+            clearing_price: 0,
+            curve_param: 0,
+            sale_reserve: 0,
+            min_raise: 0,
+            raised: 0,
+            burn_unsold: false,
+            open_auctions: 0,
+            pending_flips: 0,
+            quote_token_program: spl_token::id(),
+        }
+    }
+}
+
+/// Maximum protocol fee, 100% in basis points
+pub const MAX_FEE_BPS: u16 = 10000;
+
+/// Global protocol configuration, created once by the program upgrade authority
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Config{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Key allowed to update the config
+    pub authority: Pubkey,
+    /// Receives the protocol fee of every payout
+    pub treasury: Pubkey,
+    /// Protocol fee in basis points
+    pub fee_bps: u16,
+    /// Lamports paid to the treasury so far
+    pub total_fees: u64,
+}
+impl ProgramAccount for Config {
+    const ACCOUNT_TYPE: AccountType = AccountType::Config;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 32 + 2 + 8;
+}
+impl Config {
+    /// Protocol fee taken out of amount
+    pub fn fee_for(&self, amount: u64) -> Result<u64, ProgramError> {
+        let fee = amount as u128 * self.fee_bps as u128 / MAX_FEE_BPS as u128;
+        u64::try_from(fee).map_err(|_| TokenError::Overflow.into())
+    }
+}
+
+/// Maximum number of beneficiaries sharing the proceeds of a vault
+pub const MAX_BENEFICIARIES: usize = 8;
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy, Default)]
+pub struct Beneficiary{
+    pub address: Pubkey,
+    /// Weight of the beneficiary in the split
+    pub share: u16,
+    /// Lamports accrued and not yet withdrawn
+    pub balance: u64,
+}
+impl Beneficiary {
+    pub const LEN: usize = 32 + 2 + 8;
+}
+
+/// Proceeds of a vault shared between its beneficiaries, the account holds the accrued lamports
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Proceeds{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Data account of the vault
+    pub vault: Pubkey,
+    /// Number of used entries in beneficiaries
+    pub count: u8,
+    pub beneficiaries: [Beneficiary; MAX_BENEFICIARIES],
+}
+impl ProgramAccount for Proceeds {
+    const ACCOUNT_TYPE: AccountType = AccountType::Proceeds;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 1 + Beneficiary::LEN * MAX_BENEFICIARIES;
+}
+impl Proceeds {
+    /// Credits amount to the beneficiaries by share, rounding dust goes to the last beneficiary
+    pub fn credit(&mut self, amount: u64) -> Result<(), ProgramError> {
+        let count = self.count as usize;
+        let total_shares: u128 = self.beneficiaries[..count].iter().map(|b| b.share as u128).sum();
+        if total_shares == 0
+        {
+            return Err(TokenError::InvalidBeneficiaries.into());
+        }
+        let mut remaining = amount;
+        for (index, beneficiary) in self.beneficiaries[..count].iter_mut().enumerate() {
+            let part = if index + 1 == count {
+                remaining
+            } else {
+                (amount as u128 * beneficiary.share as u128 / total_shares) as u64
+            };
+            remaining -= part;
+            beneficiary.balance = beneficiary.balance.checked_add(part).ok_or(TokenError::Overflow)?;
+        }
+        Ok(())
+    }
+    pub fn find_mut(&mut self, address: &Pubkey) -> Option<&mut Beneficiary> {
+        let count = self.count as usize;
+        self.beneficiaries[..count].iter_mut().find(|b| b.address == *address)
+    }
+}
+
+/// Fixed point scale of the stake pool reward per token
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Fractions staked in a vault, the account holds the undistributed auction proceeds
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct StakePool{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Data account of the vault
+    pub vault: Pubkey,
+    pub total_staked: u64,
+    /// Lamports earned per staked fraction, scaled by REWARD_PRECISION
+    pub reward_per_token: u128,
+    /// Lamports distributed to the stakers since the pool was created
+    pub total_rewards: u64,
+}
+impl ProgramAccount for StakePool {
+    const ACCOUNT_TYPE: AccountType = AccountType::StakePool;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 8 + 16 + 8;
+}
+impl StakePool {
+    /// Distributes amount between the staked fractions
+    pub fn distribute(&mut self, amount: u64) -> ProgramResult {
+        if self.total_staked == 0
+        {
+            return Err(TokenError::InvalidStakePool.into());
+        }
+        let increment = (amount as u128)
+            .checked_mul(REWARD_PRECISION)
+            .ok_or(TokenError::Overflow)?
+            / self.total_staked as u128;
+        self.reward_per_token = self.reward_per_token.checked_add(increment).ok_or(TokenError::Overflow)?;
+        self.total_rewards = self.total_rewards.checked_add(amount).ok_or(TokenError::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Fractions staked by one holder in a stake pool
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct StakeEntry{
+    pub account_type: AccountType,
+    pub version: u8,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Reward per token of the pool when the entry was last settled
+    pub reward_per_token_paid: u128,
+    /// Lamports earned and not yet claimed
+    pub pending: u64,
+    /// Stake times averaged by amount, the whole amount counts as held since then
+    pub staked_at: u64,
+}
+impl ProgramAccount for StakeEntry {
+    const ACCOUNT_TYPE: AccountType = AccountType::StakeEntry;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 32 + 8 + 16 + 8 + 8;
+}
+impl StakeEntry {
+    /// Adds amount staked at now, staked_at moves to the average of the stake times weighted by amount
+    pub fn add_stake(&mut self, now: u64, amount: u64) -> ProgramResult {
+        let total = self.amount.checked_add(amount).ok_or(TokenError::Overflow)?;
+        if total > 0
+        {
+            let weighted = self.amount as u128 * self.staked_at as u128 + amount as u128 * now as u128;
+            self.staked_at = (weighted / total as u128) as u64;
+        }
+        self.amount = total;
+        Ok(())
+    }
+    /// Moves the rewards earned since the last settlement into pending
+    pub fn settle(&mut self, pool: &StakePool) -> ProgramResult {
+        let earned = (self.amount as u128)
+            .checked_mul(pool.reward_per_token - self.reward_per_token_paid)
+            .ok_or(TokenError::Overflow)?
+            / REWARD_PRECISION;
+        let earned = u64::try_from(earned).map_err(|_| TokenError::Overflow)?;
+        self.pending = self.pending.checked_add(earned).ok_or(TokenError::Overflow)?;
+        self.reward_per_token_paid = pool.reward_per_token;
+        Ok(())
+    }
+}
+
+/// Time fraction holders can vote on a proposal
+pub const VOTING_PERIOD: u64 = 3 * 86400;
+/// Delay between the end of the vote and the execution of a passed proposal
+pub const TIMELOCK: u64 = 2 * 86400;
+/// Votes in favour needed for a proposal to pass, in basis points of the circulating supply
+pub const QUORUM_BPS: u64 = 2000;
+/// Highest auction inflation a proposal can set, 10% of the supply per auction
+pub const MAX_INFLATION_BPS: u16 = 1000;
+
+/// Change of the vault parameters voted by the fraction holders
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Proposal{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Data account of the vault
+    pub vault: Pubkey,
+    pub proposer: Pubkey,
+    pub reserve_price: u64,
+    pub auction_interval: u64,
+    pub inflation_bps: u16,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    /// Votes in favour needed to pass, snapshotted from the supply at creation
+    pub quorum: u64,
+    pub voting_ends_at: u64,
+    pub executable_at: u64,
+    pub executed: bool,
+}
+impl ProgramAccount for Proposal {
+    const ACCOUNT_TYPE: AccountType = AccountType::Proposal;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 32 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+impl Proposal {
+    pub fn passed(&self) -> bool {
+        self.votes_for > self.votes_against && self.votes_for >= self.quorum
+    }
+    /// Votes are cast until voting_ends_at, after it the escrowed fractions can be withdrawn
+    pub fn voting_open(&self, now: u64) -> bool {
+        now < self.voting_ends_at
+    }
+    /// A passed proposal can't be executed before executable_at
+    pub fn timelocked(&self, now: u64) -> bool {
+        now < self.executable_at
+    }
+}
+
+/// Fractions escrowed by a holder to vote on a proposal, released once voting ends
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Vote{
+    pub account_type: AccountType,
+    pub version: u8,
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    /// Fractions escrowed, 0 once withdrawn
+    pub amount: u64,
+    pub support: bool,
+}
+impl ProgramAccount for Vote {
+    const ACCOUNT_TYPE: AccountType = AccountType::Vote;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 32 + 8 + 1;
+}
+
+/// Time fraction holders have to accept a buyout
+pub const BUYOUT_PERIOD: u64 = 7 * 86400;
+/// Fractions that have to accept a buyout, in basis points of the circulating supply
+pub const BUYOUT_ACCEPT_BPS: u64 = 5000;
+
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum BuyoutStatus {
+    Open,
+    Accepted,
+    Refunded,
+}
+
+/// Offer for the whole nft, escrowed in the account or in its associated account of an SPL quote mint
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Buyout{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Data account of the vault
+    pub vault: Pubkey,
+    pub bidder: Pubkey,
+    /// Quote offered for the nft
+    pub price: u64,
+    /// Fractions escrowed to accept the offer
+    pub votes_for: u64,
+    /// Fractions needed to accept, snapshotted from the circulating supply at creation
+    pub threshold: u64,
+    pub deadline: u64,
+    pub status: BuyoutStatus,
+    /// Fractions outside the vault when the offer was accepted, they share the price
+    pub redeemable_supply: u64,
+    /// Fractions burnt for their share of the price
+    pub redeemed: u64,
+}
+impl ProgramAccount for Buyout {
+    const ACCOUNT_TYPE: AccountType = AccountType::Buyout;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8;
+}
+impl Buyout {
+    /// Fractions needed to accept an offer made while circulating fractions are outside the vault
+    pub fn threshold_for(circulating: u64) -> u64 {
+        (circulating as u128 * BUYOUT_ACCEPT_BPS as u128 / 10000) as u64
+    }
+    pub fn accepted(&self) -> bool {
+        self.votes_for >= self.threshold
+    }
+    /// An offer is refunded once it expired without acceptance or another offer bought the nft
+    pub fn refundable(&self, now: u64, bought_out: bool) -> bool {
+        bought_out || (now >= self.deadline && !self.accepted())
+    }
+    /// Redeems amount fractions for their share of the price. Shares are taken off the running total,
+    /// so the rounding remainder goes to the last redeemer
+    pub fn redeem(&mut self, amount: u64) -> Result<u64, ProgramError> {
+        let redeemed = self.redeemed.checked_add(amount).ok_or(TokenError::Overflow)?;
+        if redeemed > self.redeemable_supply
+        {
+            msg!("Only {} fractions are redeemable",self.redeemable_supply - self.redeemed);
+            return Err(TokenError::InvalidBuyout.into());
+        }
+        let paid = |redeemed: u64| (self.price as u128 * redeemed as u128)
+            .checked_div(self.redeemable_supply as u128)
+            .unwrap_or(0) as u64;
+        let payout = paid(redeemed) - paid(self.redeemed);
+        self.redeemed = redeemed;
+        Ok(payout)
+    }
+}
+
+/// Number of balance checkpoints kept by a holder record, older ones are dropped
+pub const MAX_CHECKPOINTS: usize = 16;
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy, Default)]
+pub struct Checkpoint{
+    pub timestamp: u64,
+    /// Fractions held from timestamp on
+    pub balance: u64,
+}
+impl Checkpoint {
+    pub const LEN: usize = 8 + 8;
+}
+
+/// Fractions held by a wallet in its associated token account, kept up to date by the transfer hook.
+/// The account is owned by the transfer hook program of the fraction mint
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct HolderRecord{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Fraction mint
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    /// Associated token account of owner, transfers of other accounts are not tracked
+    pub token_account: Pubkey,
+    pub balance: u64,
+    /// Start of the current holding, reset once the balance drops to zero
+    pub first_acquired_at: u64,
+    /// Number of used entries in checkpoints
+    pub count: u8,
+    /// Balance changes, oldest first
+    pub checkpoints: [Checkpoint; MAX_CHECKPOINTS],
+}
+impl ProgramAccount for HolderRecord {
+    const ACCOUNT_TYPE: AccountType = AccountType::HolderRecord;
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 1 + 32 + 32 + 32 + 8 + 8 + 1 + Checkpoint::LEN * MAX_CHECKPOINTS;
+}
+impl HolderRecord {
+    /// Records the balance of the token account after a transfer at now
+    pub fn record_balance(&mut self, now: u64, balance: u64) {
+        if balance == 0
+        {
+            self.first_acquired_at = 0;
+        } else if self.balance == 0
+        {
+            self.first_acquired_at = now;
+        }
+        self.balance = balance;
+        let count = self.count as usize;
+        if count > 0 && self.checkpoints[count - 1].timestamp == now
+        {
+            self.checkpoints[count - 1].balance = balance;
+            return;
+        }
+        // a run of increases keeps one checkpoint at its latest time, so dust sent to the
+        // holder can't evict older history and the window before it reads the lower balance
+        if count >= 2
+            && balance >= self.checkpoints[count - 1].balance
+            && self.checkpoints[count - 1].balance >= self.checkpoints[count - 2].balance
+        {
+            self.checkpoints[count - 1] = Checkpoint{timestamp: now, balance};
+            return;
+        }
+        if count == MAX_CHECKPOINTS
+        {
+            self.checkpoints.rotate_left(1);
+            self.checkpoints[MAX_CHECKPOINTS - 1] = Checkpoint{timestamp: now, balance};
+        } else {
+            self.checkpoints[count] = Checkpoint{timestamp: now, balance};
+            self.count += 1;
+        }
+    }
+    /// Balance held at timestamp, None when it predates the oldest kept checkpoint
+    pub fn balance_at(&self, timestamp: u64) -> Option<u64> {
+        let count = self.count as usize;
+        self.checkpoints[..count]
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.timestamp <= timestamp)
+            .map(|checkpoint| checkpoint.balance)
+    }
+    /// Lowest balance held from timestamp until now, None when timestamp predates the oldest kept checkpoint
+    pub fn min_balance_since(&self, timestamp: u64) -> Option<u64> {
+        let count = self.count as usize;
+        let held_at_start = self.balance_at(timestamp)?;
+        Some(self.checkpoints[..count]
+            .iter()
+            .filter(|checkpoint| checkpoint.timestamp > timestamp)
+            .map(|checkpoint| checkpoint.balance)
+            .fold(held_at_start, u64::min))
+    }
+    /// Lowest balance held from timestamp until now. When timestamp predates the kept checkpoints
+    /// the lowest kept one counts, as long as the current holding started by timestamp
+    pub fn held_balance_since(&self, timestamp: u64) -> u64 {
+        if let Some(balance) = self.min_balance_since(timestamp)
+        {
+            return balance;
+        }
+        if self.balance == 0 || self.first_acquired_at > timestamp
+        {
+            return 0;
+        }
+        let count = self.count as usize;
+        self.checkpoints[..count]
+            .iter()
+            .map(|checkpoint| checkpoint.balance)
+            .fold(self.balance, u64::min)
+    }
+    /// Seconds the current holding has lasted, zero for an empty balance
+    pub fn held_for(&self, now: u64) -> Result<u64, ProgramError> {
+        if self.balance == 0
+        {
+            return Ok(0);
+        }
+        seconds_since(now, self.first_acquired_at)
+    }
+}
+
+/// Fractions a wallet bought in the primary sale of a vault
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Purchase{
+    pub account_type: AccountType,
+    pub version: u8,
+    /// Data account of the vault
+    pub vault: Pubkey,
+    pub buyer: Pubkey,
+    pub bought: u64,
+    /// Quote paid above the floor price of a refundable dutch sale, escrowed in the vault until claimed
+    pub premium: u64,
+    /// Quote paid into the sale reserve of a crowdfund, refunded when the raise fails
+    pub paid: u64,
+}
+impl ProgramAccount for Purchase {
+    const ACCOUNT_TYPE: AccountType = AccountType::Purchase;
+    const VERSION: u8 = 2;
+    const LEN: usize = 1 + 1 + 32 + 32 + 8 + 8 + 8;
+}
+impl Purchase {
+    /// Premium owed to the vault once the dutch sale cleared at clearing_price over the floor price
+    pub fn owed_premium(&self, clearing_price: u64, floor_price: u64) -> Result<u64, ProgramError> {
+        let owed = self.bought as u128 * clearing_price.saturating_sub(floor_price) as u128;
+        u64::try_from(owed.min(self.premium as u128)).map_err(|_| TokenError::Overflow.into())
+    }
+    /// Adds token fractions bought, within the allowlist cap of the buyer and the wallet cap of the vault
+    pub fn add_bought(&mut self, token: u64, allowlist_cap: Option<u64>, max_per_wallet: u64) -> ProgramResult {
+        let bought = self.bought.checked_add(token).ok_or(TokenError::Overflow)?;
+        if let Some(cap) = allowlist_cap {
+            if bought > cap
+            {
+                msg!("Only {} more fractions can be bought",cap.saturating_sub(self.bought));
+                return Err(TokenError::AllowanceExceeded.into());
+            }
+        }
+        if max_per_wallet > 0 && bought > max_per_wallet
+        {
+            msg!("A wallet can buy at most {} fractions",max_per_wallet);
+            return Err(TokenError::WalletCapExceeded.into());
+        }
+        self.bought = bought;
+        Ok(())
+    }
+    /// Quote refunded for amount of the fractions bought in a failed crowdfund, at the average price paid
+    pub fn refund_for(&self, amount: u64) -> Result<u64, ProgramError> {
+        if amount > self.bought
+        {
+            return Err(TokenError::TokenFinished.into());
+        }
+        Ok((self.paid as u128 * amount as u128 / self.bought as u128) as u64)
+    }
+    /// Removes amount refunded fractions and the refund paid for them
+    pub fn refund(&mut self, amount: u64, refund: u64) -> ProgramResult {
+        self.paid = self.paid.checked_sub(refund).ok_or(TokenError::Overflow)?;
+        self.bought = self.bought.checked_sub(amount).ok_or(TokenError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{account_infos, install_stubs, TestAccount};
+
+    fn legacy_vault() -> LegacyNftDetails {
+        LegacyNftDetails{
+            number_of_tokens: 1_000,
+            price: 5,
+            nft_mint: Pubkey::new_unique(),
+            nft_escrow: Pubkey::new_unique(),
+            nft_owner: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            create_at: 1_700_000_000,
+            days: 3.0,
+            remaining_token: 400,
+        }
+    }
+
+    fn assert_legacy_fields(escrow: &NftDetails, legacy: &LegacyNftDetails) {
+        assert_eq!(escrow.account_type, AccountType::NftDetails);
+        assert_eq!(escrow.version, NftDetails::VERSION);
+        assert_eq!(escrow.number_of_tokens, legacy.number_of_tokens);
+        assert_eq!(escrow.price, legacy.price);
+        assert_eq!(escrow.nft_mint, legacy.nft_mint);
+        assert_eq!(escrow.nft_escrow, legacy.nft_escrow);
+        assert_eq!(escrow.nft_owner, legacy.nft_owner);
+        assert_eq!(escrow.token_mint, legacy.token_mint);
+        assert_eq!(escrow.create_at, legacy.create_at);
+        assert_eq!(escrow.remaining_token, legacy.remaining_token);
+    }
+
+    #[test]
+    fn upgrade_maps_the_legacy_layout() {
+        let legacy = legacy_vault();
+        let escrow = NftDetails::upgrade(&legacy.try_to_vec().unwrap()).unwrap();
+        assert_legacy_fields(&escrow, &legacy);
+        assert_eq!(escrow.period_start, legacy.create_at);
+        assert_eq!(escrow.auction_interval, DEFAULT_AUCTION_INTERVAL);
+        assert_eq!(escrow.token_program, spl_token::id());
+        assert_eq!(escrow.try_to_vec().unwrap().len(), NftDetails::LEN);
+    }
+
+    #[test]
+    fn upgrade_rejects_versioned_and_unknown_layouts() {
+        let current = NftDetails::from(legacy_vault()).try_to_vec().unwrap();
+        assert_eq!(NftDetails::upgrade(&current), Err(TokenError::InvalidAccountVersion.into()));
+        // the legacy body behind a header is not a layout that was ever written
+        let mut headed = vec![AccountType::NftDetails as u8, 0];
+        headed.extend(legacy_vault().try_to_vec().unwrap());
+        assert_eq!(NftDetails::upgrade(&headed), Err(TokenError::InvalidAccountVersion.into()));
+        let mut other = current.clone();
+        other[0] = AccountType::Auction as u8;
+        assert_eq!(NftDetails::upgrade(&other), Err(TokenError::InvalidAccountType.into()));
+        assert_eq!(NftDetails::upgrade(&[]), Err(TokenError::InvalidAccountType.into()));
+    }
+
+    fn holder() -> HolderRecord {
+        HolderRecord{
+            account_type: AccountType::HolderRecord,
+            version: HolderRecord::VERSION,
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            balance: 0,
+            first_acquired_at: 0,
+            count: 0,
+            checkpoints: [Checkpoint::default(); MAX_CHECKPOINTS],
+        }
+    }
+
+    #[test]
+    fn record_balance_merges_runs_of_increases() {
+        let mut record = holder();
+        record.record_balance(10, 100);
+        record.record_balance(20, 50);
+        for now in 30..30 + 2 * MAX_CHECKPOINTS as u64 {
+            record.record_balance(now, 50 + now);
+        }
+        assert_eq!(record.count, 3);
+        assert_eq!(record.balance, 50 + 29 + 2 * MAX_CHECKPOINTS as u64);
+        assert_eq!(record.balance_at(10), Some(100));
+        // the increases only count from the last of them
+        assert_eq!(record.min_balance_since(15), Some(50));
+        assert_eq!(record.balance_at(40), Some(50));
+        assert_eq!(record.first_acquired_at, 10);
+    }
+
+    #[test]
+    fn record_balance_keeps_every_decrease() {
+        let mut record = holder();
+        record.record_balance(10, 100);
+        record.record_balance(20, 120);
+        record.record_balance(30, 90);
+        record.record_balance(40, 95);
+        record.record_balance(50, 80);
+        assert_eq!(record.count, 5);
+        assert_eq!(record.min_balance_since(10), Some(80));
+        assert_eq!(record.min_balance_since(35), Some(80));
+        assert_eq!(record.balance_at(45), Some(95));
+    }
+
+    #[test]
+    fn held_balance_since_falls_back_to_the_kept_checkpoints() {
+        let mut record = holder();
+        record.record_balance(10, 500);
+        for now in 20..20 + MAX_CHECKPOINTS as u64 {
+            record.record_balance(now, 520 - now);
+        }
+        let lowest = 520 - (19 + MAX_CHECKPOINTS as u64);
+        assert_eq!(record.min_balance_since(15), None);
+        assert_eq!(record.held_balance_since(15), lowest);
+        // the holding started after the window opened
+        assert_eq!(record.held_balance_since(5), 0);
+        record.record_balance(100, 0);
+        assert_eq!(record.held_balance_since(15), 0);
+    }
+
+    fn stake_entry() -> StakeEntry {
+        StakeEntry{
+            account_type: AccountType::StakeEntry,
+            version: StakeEntry::VERSION,
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 0,
+            reward_per_token_paid: 0,
+            pending: 0,
+            staked_at: 0,
+        }
+    }
+
+    fn stake_pool() -> StakePool {
+        StakePool{
+            account_type: AccountType::StakePool,
+            version: StakePool::VERSION,
+            vault: Pubkey::new_unique(),
+            total_staked: 0,
+            reward_per_token: 0,
+            total_rewards: 0,
+        }
+    }
+
+    /// Stakes amount into the pool the way Stake does, settling the entry first
+    fn stake(pool: &mut StakePool, entry: &mut StakeEntry, amount: u64) {
+        entry.settle(pool).unwrap();
+        entry.add_stake(0, amount).unwrap();
+        pool.total_staked += amount;
+    }
+
+    #[test]
+    fn add_stake_averages_the_stake_times() {
+        let mut entry = stake_entry();
+        entry.add_stake(1_000, 300).unwrap();
+        assert_eq!(entry.staked_at, 1_000);
+        entry.add_stake(2_000, 100).unwrap();
+        assert_eq!(entry.amount, 400);
+        assert_eq!(entry.staked_at, 1_250);
+        // a dust top up barely moves the holding start
+        entry.add_stake(100_000, 1).unwrap();
+        assert_eq!(entry.staked_at, 1_496);
+    }
+
+    #[test]
+    fn settle_unsold_burns_out_of_the_supply() {
+        let mut escrow = NftDetails::from(legacy_vault());
+        escrow.burn_unsold = true;
+        escrow.settle_unsold().unwrap();
+        assert_eq!(escrow.remaining_token, 0);
+        assert_eq!(escrow.number_of_tokens, 600);
+
+        // handed to the curator the fractions still circulate
+        let mut escrow = NftDetails::from(legacy_vault());
+        escrow.settle_unsold().unwrap();
+        assert_eq!(escrow.remaining_token, 0);
+        assert_eq!(escrow.number_of_tokens, 1_000);
+    }
+
+    fn curve_vault(sale_mode: SaleMode, price: u64, curve_param: u64) -> NftDetails {
+        let mut escrow = NftDetails::from(legacy_vault());
+        escrow.sale_mode = sale_mode;
+        escrow.price = price;
+        escrow.curve_param = curve_param;
+        escrow.number_of_tokens = 100 * CURVE_UNIT;
+        escrow.remaining_token = escrow.number_of_tokens;
+        escrow
+    }
+
+    /// Records a purchase of token fractions along the curve, returning its cost
+    fn buy_on_curve(escrow: &mut NftDetails, token: u64) -> u64 {
+        let cost = escrow.sale_cost(0, token).unwrap();
+        escrow.remaining_token -= token;
+        escrow.sale_reserve += cost;
+        cost
+    }
+
+    #[test]
+    fn curve_pow_raises_fixed_point_bases() {
+        assert_eq!(curve_pow(3 * CURVE_PRECISION, 0).unwrap(), CURVE_PRECISION);
+        assert_eq!(curve_pow(2 * CURVE_PRECISION, 10).unwrap(), 1024 * CURVE_PRECISION);
+        assert_eq!(curve_pow(3 * CURVE_PRECISION / 2, 3).unwrap(), 3_375 * CURVE_PRECISION / 1000);
+        assert_eq!(curve_pow(2 * CURVE_PRECISION, 200), Err(TokenError::Overflow.into()));
+    }
+
+    #[test]
+    fn linear_curve_prices_every_fraction_one_slope_higher() {
+        // the k-th fraction costs 1 + k
+        let escrow = curve_vault(SaleMode::LinearCurve, 1, CURVE_UNIT);
+        assert_eq!(escrow.sale_price_at(0).unwrap(), 1);
+        assert_eq!(escrow.sale_cost(0, 4).unwrap(), 1 + 2 + 3 + 4);
+        // a slope of 3 per whole fraction rounds down inside a step
+        let escrow = curve_vault(SaleMode::LinearCurve, 5, 3);
+        assert_eq!(escrow.sale_cost(0, CURVE_UNIT).unwrap(), 5 * CURVE_UNIT + 3 * (CURVE_UNIT - 1) / 2);
+    }
+
+    #[test]
+    fn exponential_curve_sums_full_steps_and_prices_the_partial_step() {
+        // 10% growth every whole fraction
+        let escrow = curve_vault(SaleMode::ExponentialCurve, 100, 1000);
+        assert_eq!(escrow.sale_cost(0, CURVE_UNIT).unwrap(), 100 * CURVE_UNIT);
+        // 100 + 110 per base unit over two steps
+        assert_eq!(escrow.sale_cost(0, 2 * CURVE_UNIT).unwrap(), 210 * CURVE_UNIT);
+        // half of the third step at 121
+        assert_eq!(escrow.sale_cost(0, 5 * CURVE_UNIT / 2).unwrap(), 210 * CURVE_UNIT + 121 * CURVE_UNIT / 2);
+        let mut escrow = escrow;
+        buy_on_curve(&mut escrow, 2 * CURVE_UNIT);
+        assert_eq!(escrow.sale_price_at(0).unwrap(), 121);
+    }
+
+    #[test]
+    fn curve_purchases_split_at_any_point_cost_the_same() {
+        for mut escrow in [
+            curve_vault(SaleMode::LinearCurve, 7, 3),
+            curve_vault(SaleMode::ExponentialCurve, 7, 250),
+        ] {
+            buy_on_curve(&mut escrow, CURVE_UNIT / 3);
+            let whole = escrow.sale_cost(0, 5 * CURVE_UNIT + 17).unwrap();
+            let first = buy_on_curve(&mut escrow, 2 * CURVE_UNIT + 11);
+            let rest = escrow.sale_cost(0, 3 * CURVE_UNIT + 6).unwrap();
+            assert_eq!(first + rest, whole);
+        }
+    }
+
+    #[test]
+    fn curve_sell_back_round_trips_without_draining_the_reserve() {
+        for mut escrow in [
+            curve_vault(SaleMode::LinearCurve, 7, 3),
+            curve_vault(SaleMode::ExponentialCurve, 7, 250),
+        ] {
+            let earlier = buy_on_curve(&mut escrow, 4 * CURVE_UNIT + 1);
+            let cost = buy_on_curve(&mut escrow, 3 * CURVE_UNIT / 2);
+            assert_eq!(escrow.curve_refund(3 * CURVE_UNIT / 2).unwrap(), cost);
+            escrow.remaining_token += 3 * CURVE_UNIT / 2;
+            escrow.sale_reserve -= cost;
+            assert_eq!(escrow.sale_reserve, earlier);
+            // a reserve short of the curve pays out what it holds
+            escrow.sale_reserve = earlier - 1;
+            assert_eq!(escrow.curve_refund(4 * CURVE_UNIT + 1).unwrap(), earlier - 1);
+            assert_eq!(escrow.curve_refund(4 * CURVE_UNIT + 2), Err(TokenError::TokenFinished.into()));
+        }
+    }
+
+    #[test]
+    fn curve_overflow_is_an_error() {
+        let mut escrow = curve_vault(SaleMode::LinearCurve, u64::MAX, u64::MAX);
+        escrow.number_of_tokens = u64::MAX;
+        escrow.remaining_token = u64::MAX;
+        assert_eq!(escrow.sale_cost(0, u64::MAX), Err(TokenError::Overflow.into()));
+        let mut escrow = curve_vault(SaleMode::ExponentialCurve, 1, 10000);
+        escrow.number_of_tokens = u64::MAX;
+        escrow.remaining_token = u64::MAX;
+        assert_eq!(escrow.sale_cost(0, 200 * CURVE_UNIT), Err(TokenError::Overflow.into()));
+    }
+
+    fn dutch_vault() -> NftDetails {
+        let mut escrow = NftDetails::from(legacy_vault());
+        escrow.sale_mode = SaleMode::Dutch;
+        escrow.start_price = 100;
+        escrow.price = 20;
+        escrow.auction_interval = 1_000;
+        escrow
+    }
+
+    #[test]
+    fn dutch_price_decays_to_the_floor_over_the_buy_period() {
+        let escrow = dutch_vault();
+        let start = escrow.create_at;
+        assert_eq!(escrow.sale_price_at(start).unwrap(), 100);
+        assert_eq!(escrow.sale_price_at(start + 500).unwrap(), 60);
+        // the decay rounds in favour of the vault
+        assert_eq!(escrow.sale_price_at(start + 333).unwrap(), 74);
+        assert_eq!(escrow.sale_price_at(start + 1_000).unwrap(), 20);
+        assert_eq!(escrow.sale_price_at(start + 50_000).unwrap(), 20);
+        assert_eq!(escrow.sale_cost(start + 500, 3).unwrap(), 180);
+    }
+
+    #[test]
+    fn owed_premium_keeps_the_part_above_the_clearing_price_for_the_buyer() {
+        let floor = 20;
+        // bought 10 at 60 over a floor of 20
+        let purchase = Purchase{
+            account_type: AccountType::Purchase,
+            version: Purchase::VERSION,
+            vault: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            bought: 10,
+            premium: 400,
+            paid: 0,
+        };
+        // cleared below the buyer's price, 10 * (60 - 45) is refunded
+        assert_eq!(purchase.owed_premium(45, floor).unwrap(), 250);
+        // cleared at the buyer's price, nothing is refunded
+        assert_eq!(purchase.owed_premium(60, floor).unwrap(), 400);
+        // the vault never keeps more than the buyer paid above the floor
+        assert_eq!(purchase.owed_premium(80, floor).unwrap(), 400);
+        // cleared at the floor, the whole premium is refunded
+        assert_eq!(purchase.owed_premium(floor, floor).unwrap(), 0);
+    }
+
+    fn purchase() -> Purchase {
+        Purchase{
+            account_type: AccountType::Purchase,
+            version: Purchase::VERSION,
+            vault: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            bought: 0,
+            premium: 0,
+            paid: 0,
+        }
+    }
+
+    #[test]
+    fn raise_fails_once_the_buy_period_ends_short_of_the_target() {
+        let mut escrow = NftDetails::from(legacy_vault());
+        let end = escrow.create_at + escrow.auction_interval;
+        assert!(!escrow.raise_failed(end).unwrap());
+        escrow.min_raise = 500;
+        escrow.raised = 499;
+        assert!(!escrow.raise_failed(end - 1).unwrap());
+        assert!(escrow.raise_failed(end).unwrap());
+        escrow.raised = 500;
+        assert!(!escrow.raise_failed(end).unwrap());
+    }
+
+    #[test]
+    fn crowdfund_refunds_what_every_buyer_paid_along_the_curve() {
+        let mut escrow = curve_vault(SaleMode::ExponentialCurve, 7, 250);
+        escrow.min_raise = u64::MAX;
+        let mut buyers = [purchase(), purchase()];
+        for (index, token) in [(0, 2 * CURVE_UNIT), (1, CURVE_UNIT), (0, CURVE_UNIT / 2)] {
+            buyers[index].paid += buy_on_curve(&mut escrow, token);
+            buyers[index].bought += token;
+        }
+        let [mut early, late] = buyers;
+        // the later fractions cost more, each buyer gets back their own average price
+        assert!(late.paid * 5 > early.paid * 2);
+        let early_paid = early.paid;
+        let partial = early.refund_for(CURVE_UNIT).unwrap();
+        assert_eq!(partial, (early_paid as u128 * 2 / 5) as u64);
+        early.refund(CURVE_UNIT, partial).unwrap();
+        let rest = early.refund_for(3 * CURVE_UNIT / 2).unwrap();
+        assert_eq!(partial + rest, early_paid);
+        assert_eq!(early.refund_for(3 * CURVE_UNIT / 2 + 1), Err(TokenError::TokenFinished.into()));
+        early.refund(3 * CURVE_UNIT / 2, rest).unwrap();
+        assert_eq!((early.bought, early.paid), (0, 0));
+        // the reserve covers every refund exactly
+        assert_eq!(partial + rest + late.refund_for(CURVE_UNIT).unwrap(), escrow.sale_reserve);
+    }
+
+    #[test]
+    fn crowdfund_refunds_dutch_purchases_at_their_own_price() {
+        let escrow = dutch_vault();
+        let mut buyer = purchase();
+        for (elapsed, token) in [(0, 3), (500, 1)] {
+            buyer.paid += escrow.sale_cost(escrow.create_at + elapsed, token).unwrap();
+            buyer.bought += token;
+        }
+        assert_eq!(buyer.paid, 360);
+        assert_eq!(buyer.refund_for(2).unwrap(), 180);
+        assert_eq!(buyer.refund_for(4).unwrap(), 360);
+    }
+
+    #[test]
+    fn distribute_pays_stakers_pro_rata() {
+        let mut pool = stake_pool();
+        let mut small = stake_entry();
+        let mut large = stake_entry();
+        stake(&mut pool, &mut small, 100);
+        stake(&mut pool, &mut large, 300);
+        pool.distribute(1_000).unwrap();
+        small.settle(&pool).unwrap();
+        large.settle(&pool).unwrap();
+        assert_eq!((small.pending, large.pending), (250, 750));
+
+        // a later staker only shares later proceeds
+        let mut late = stake_entry();
+        stake(&mut pool, &mut late, 600);
+        pool.distribute(1_000).unwrap();
+        for entry in [&mut small, &mut large, &mut late] {
+            entry.settle(&pool).unwrap();
+        }
+        assert_eq!((small.pending, large.pending, late.pending), (350, 1_050, 600));
+        assert_eq!(pool.total_rewards, 2_000);
+    }
+
+    #[test]
+    fn distribute_rounds_down_and_never_pays_out_more_than_it_received() {
+        let mut pool = stake_pool();
+        let mut entries = [stake_entry(), stake_entry(), stake_entry()];
+        for entry in entries.iter_mut() {
+            stake(&mut pool, entry, 1);
+        }
+        pool.distribute(100).unwrap();
+        let paid: u64 = entries.iter_mut().map(|entry| {
+            entry.settle(&pool).unwrap();
+            entry.pending
+        }).sum();
+        // the dust stays in the pool account
+        assert_eq!(paid, 99);
+    }
+
+    #[test]
+    fn distribute_without_stake_leaves_the_pool_untouched() {
+        let mut pool = stake_pool();
+        let before = pool.clone();
+        // settlement pays the curator instead of a pool nobody can claim from
+        assert_eq!(pool.distribute(1_000), Err(TokenError::InvalidStakePool.into()));
+        assert_eq!(pool, before);
+        // the first staker after it starts from nothing
+        let mut entry = stake_entry();
+        stake(&mut pool, &mut entry, 10);
+        entry.settle(&pool).unwrap();
+        assert_eq!(entry.pending, 0);
+    }
+
+    fn proposal() -> Proposal {
+        Proposal{
+            account_type: AccountType::Proposal,
+            version: Proposal::VERSION,
+            vault: Pubkey::new_unique(),
+            proposer: Pubkey::new_unique(),
+            reserve_price: 0,
+            auction_interval: DEFAULT_AUCTION_INTERVAL,
+            inflation_bps: DEFAULT_INFLATION_BPS,
+            votes_for: 0,
+            votes_against: 0,
+            quorum: 200,
+            voting_ends_at: 1_000 + VOTING_PERIOD,
+            executable_at: 1_000 + VOTING_PERIOD + TIMELOCK,
+            executed: false,
+        }
+    }
+
+    #[test]
+    fn proposal_passes_with_a_majority_at_quorum() {
+        let mut proposal = proposal();
+        proposal.votes_for = 199;
+        assert!(!proposal.passed());
+        proposal.votes_for = 200;
+        assert!(proposal.passed());
+        // a tie fails
+        proposal.votes_against = 200;
+        assert!(!proposal.passed());
+        proposal.votes_for = 201;
+        assert!(proposal.passed());
+        // a majority short of quorum fails
+        proposal.votes_for = 150;
+        proposal.votes_against = 0;
+        assert!(!proposal.passed());
+    }
+
+    #[test]
+    fn proposal_voting_and_timelock_end_at_their_timestamps() {
+        let proposal = proposal();
+        assert!(proposal.voting_open(proposal.voting_ends_at - 1));
+        assert!(!proposal.voting_open(proposal.voting_ends_at));
+        assert!(proposal.timelocked(proposal.voting_ends_at));
+        assert!(proposal.timelocked(proposal.executable_at - 1));
+        assert!(!proposal.timelocked(proposal.executable_at));
+    }
+
+    #[test]
+    fn first_clearing_price_sets_the_average() {
+        let mut escrow = NftDetails::from(legacy_vault());
+        // the sale price values the vault before any auction
+        assert_eq!(escrow.implied_valuation().unwrap(), 5_000);
+        escrow.record_clearing_price(10_000, 80, 10).unwrap();
+        assert_eq!(escrow.twap_price, 8 * PRICE_SCALE);
+        assert_eq!(escrow.twap_updated_at, 10_000);
+        assert_eq!(escrow.valuation, 8_000);
+        // an auction without tokens leaves the average alone
+        escrow.record_clearing_price(20_000, 0, 0).unwrap();
+        assert_eq!((escrow.twap_price, escrow.twap_updated_at), (8 * PRICE_SCALE, 10_000));
+    }
+
+    #[test]
+    fn clearing_price_after_a_full_window_replaces_the_average() {
+        let mut escrow = NftDetails::from(legacy_vault());
+        escrow.record_clearing_price(10_000, 80, 10).unwrap();
+        escrow.record_clearing_price(10_000 + 3 * TWAP_WINDOW, 20, 10).unwrap();
+        assert_eq!(escrow.twap_price, 2 * PRICE_SCALE);
+        assert_eq!(escrow.implied_valuation().unwrap(), 2_000);
+    }
+
+    #[test]
+    fn clearing_prices_within_a_window_are_weighted_by_elapsed_time() {
+        let mut escrow = NftDetails::from(legacy_vault());
+        escrow.record_clearing_price(10_000, 80, 10).unwrap();
+        // a quarter of the window in, the new price of 4 weighs a quarter
+        escrow.record_clearing_price(10_000 + TWAP_WINDOW / 4, 40, 10).unwrap();
+        assert_eq!(escrow.twap_price, 7 * PRICE_SCALE);
+        // a price set again in the same second has no weight
+        escrow.record_clearing_price(10_000 + TWAP_WINDOW / 4, 1_000, 10).unwrap();
+        assert_eq!(escrow.twap_price, 7 * PRICE_SCALE);
+        assert_eq!(escrow.valuation, 7_000);
+        assert_eq!(escrow.last_auction_price, 1_000);
+    }
+
+    fn config(fee_bps: u16) -> Config {
+        Config{
+            account_type: AccountType::Config,
+            version: Config::VERSION,
+            authority: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            fee_bps,
+            total_fees: 0,
+        }
+    }
+
+    #[test]
+    fn fee_rounds_down_in_favour_of_the_payee() {
+        assert_eq!(config(250).fee_for(1_000).unwrap(), 25);
+        assert_eq!(config(250).fee_for(999).unwrap(), 24);
+        assert_eq!(config(250).fee_for(39).unwrap(), 0);
+        assert_eq!(config(0).fee_for(u64::MAX).unwrap(), 0);
+        assert_eq!(config(MAX_FEE_BPS).fee_for(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    fn proceeds(shares: &[u16]) -> Proceeds {
+        let mut proceeds = Proceeds{
+            account_type: AccountType::Proceeds,
+            version: Proceeds::VERSION,
+            vault: Pubkey::new_unique(),
+            count: shares.len() as u8,
+            beneficiaries: [Beneficiary::default(); MAX_BENEFICIARIES],
+        };
+        for (beneficiary, share) in proceeds.beneficiaries.iter_mut().zip(shares) {
+            beneficiary.address = Pubkey::new_unique();
+            beneficiary.share = *share;
+        }
+        proceeds
+    }
+
+    fn balances(proceeds: &Proceeds) -> Vec<u64> {
+        proceeds.beneficiaries[..proceeds.count as usize].iter().map(|b| b.balance).collect()
+    }
+
+    #[test]
+    fn credit_splits_by_share_and_hands_the_dust_to_the_last_beneficiary() {
+        let mut even = proceeds(&[1, 1, 1]);
+        even.credit(100).unwrap();
+        assert_eq!(balances(&even), vec![33, 33, 34]);
+        even.credit(7).unwrap();
+        assert_eq!(balances(&even), vec![35, 35, 37]);
+
+        let mut weighted = proceeds(&[6000, 3000, 1000]);
+        weighted.credit(999).unwrap();
+        assert_eq!(balances(&weighted), vec![599, 299, 101]);
+        assert_eq!(proceeds(&[0, 0]).credit(1), Err(TokenError::InvalidBeneficiaries.into()));
+    }
+
+    #[test]
+    fn fee_royalties_and_beneficiary_credits_add_up_to_the_payment() {
+        use crate::royalties::{Creator, Royalties};
+        let royalties = Royalties{
+            seller_fee_basis_points: 750,
+            creators: vec![
+                Creator{address: Pubkey::new_unique(), verified: true, share: 33},
+                Creator{address: Pubkey::new_unique(), verified: true, share: 67},
+            ],
+        };
+        for gross in [0, 1, 99, 1_001, 123_456_789, u64::MAX / 3] {
+            let mut split = proceeds(&[3, 5, 7]);
+            let fee = config(333).fee_for(gross).unwrap();
+            let royalty: u64 = royalties.split(gross - fee).unwrap().iter().map(|(_, payout)| payout).sum();
+            split.credit(gross - fee - royalty).unwrap();
+            let credited: u64 = balances(&split).iter().sum();
+            assert_eq!(fee + royalty + credited, gross);
+        }
+    }
+
+    #[test]
+    fn wallet_cap_holds_across_purchases() {
+        let mut buyer = purchase();
+        buyer.add_bought(60, None, 100).unwrap();
+        assert_eq!(buyer.add_bought(41, None, 100), Err(TokenError::WalletCapExceeded.into()));
+        assert_eq!(buyer.bought, 60);
+        buyer.add_bought(40, None, 100).unwrap();
+        assert_eq!(buyer.add_bought(1, None, 100), Err(TokenError::WalletCapExceeded.into()));
+        // no wallet cap
+        buyer.add_bought(1_000, None, 0).unwrap();
+        assert_eq!(buyer.bought, 1_100);
+    }
+
+    #[test]
+    fn allowlist_cap_holds_across_purchases() {
+        let mut buyer = purchase();
+        buyer.add_bought(30, Some(50), 100).unwrap();
+        assert_eq!(buyer.add_bought(21, Some(50), 100), Err(TokenError::AllowanceExceeded.into()));
+        buyer.add_bought(20, Some(50), 100).unwrap();
+        // the tighter wallet cap applies over a larger allowance
+        assert_eq!(buyer.add_bought(51, Some(200), 100), Err(TokenError::WalletCapExceeded.into()));
+        assert_eq!(buyer.add_bought(u64::MAX, None, 0), Err(TokenError::Overflow.into()));
+        assert_eq!(buyer.bought, 50);
+    }
+
+    #[test]
+    fn unsettled_auction_tokens_are_the_supply_not_minted_yet() {
+        let mut escrow = NftDetails::from(legacy_vault());
+        assert_eq!(escrow.unsettled_auction_tokens(1_000), 0);
+        // two auctions opened, one settled
+        escrow.number_of_tokens += 2 * escrow.auction_tokens();
+        assert_eq!(escrow.unsettled_auction_tokens(1_010), 10);
+        assert_eq!(escrow.unsettled_auction_tokens(1_020), 0);
+    }
+
+    fn buyout(price: u64, redeemable_supply: u64) -> Buyout {
+        Buyout{
+            account_type: AccountType::Buyout,
+            version: Buyout::VERSION,
+            vault: Pubkey::new_unique(),
+            bidder: Pubkey::new_unique(),
+            price,
+            votes_for: 0,
+            threshold: Buyout::threshold_for(1_000),
+            deadline: 1_000 + BUYOUT_PERIOD,
+            status: BuyoutStatus::Accepted,
+            redeemable_supply,
+            redeemed: 0,
+        }
+    }
+
+    #[test]
+    fn buyout_is_accepted_at_half_the_circulating_supply() {
+        let mut offer = buyout(1_000, 0);
+        assert_eq!(offer.threshold, 500);
+        offer.votes_for = 499;
+        assert!(!offer.accepted());
+        offer.votes_for = 500;
+        assert!(offer.accepted());
+        assert_eq!(Buyout::threshold_for(u64::MAX), u64::MAX / 2);
+    }
+
+    #[test]
+    fn buyout_is_refundable_once_expired_unaccepted_or_outbid() {
+        let mut offer = buyout(1_000, 0);
+        assert!(!offer.refundable(offer.deadline - 1, false));
+        assert!(offer.refundable(offer.deadline, false));
+        // another offer bought the nft before the deadline
+        assert!(offer.refundable(offer.deadline - 1, true));
+        // accepted offers wait for execution
+        offer.votes_for = offer.threshold;
+        assert!(!offer.refundable(offer.deadline, false));
+    }
+
+    #[test]
+    fn redeem_pays_out_the_whole_price() {
+        let mut offer = buyout(1_000, 3);
+        let payouts: Vec<u64> = (0..3).map(|_| offer.redeem(1).unwrap()).collect();
+        assert_eq!(payouts, [333, 333, 334]);
+        assert_eq!(offer.redeem(1), Err(TokenError::InvalidBuyout.into()));
+
+        let mut offer = buyout(1_000, 7);
+        let paid = offer.redeem(2).unwrap() + offer.redeem(4).unwrap() + offer.redeem(1).unwrap();
+        assert_eq!((paid, offer.redeemed), (1_000, 7));
+    }
+
+    #[test]
+    fn redeem_caps_at_the_redeemable_supply() {
+        let mut offer = buyout(u64::MAX, 10);
+        assert_eq!(offer.redeem(11), Err(TokenError::InvalidBuyout.into()));
+        assert_eq!(offer.redeem(u64::MAX), Err(TokenError::InvalidBuyout.into()));
+        assert_eq!(offer.redeem(10), Ok(u64::MAX));
+        assert_eq!(offer.redeem(0), Ok(0));
+    }
+
+    fn loaded<T: ProgramAccount>(data: Vec<u8>, owner: Pubkey, program_id: &Pubkey) -> Result<T, ProgramError> {
+        install_stubs();
+        let lamports = Rent::default().minimum_balance(data.len());
+        let infos = account_infos(&[TestAccount::new(Pubkey::new_unique(), owner, lamports, data)]);
+        T::load(&infos[0], program_id)
+    }
+
+    #[test]
+    fn load_checks_owner_type_version_and_length() {
+        let program_id = Pubkey::new_unique();
+        let auction = Auction{
+            account_type: AccountType::Auction,
+            version: Auction::VERSION,
+            vault: Pubkey::new_unique(),
+            max_price: 10,
+            max_payer: Pubkey::new_unique(),
+            num_tokens: 10,
+            day: 1,
+        };
+        let data = auction.try_to_vec().unwrap();
+        assert_eq!(loaded::<Auction>(data.clone(), program_id, &program_id), Ok(auction));
+        assert_eq!(loaded::<Auction>(data.clone(), Pubkey::new_unique(), &program_id), Err(ProgramError::IllegalOwner));
+        // another account type, padded to its length
+        let mut as_vault = data.clone();
+        as_vault.resize(NftDetails::LEN, 0);
+        assert_eq!(loaded::<NftDetails>(as_vault, program_id, &program_id), Err(TokenError::InvalidAccountType.into()));
+        let mut old_version = data.clone();
+        old_version[1] = Auction::VERSION - 1;
+        assert_eq!(loaded::<Auction>(old_version, program_id, &program_id), Err(TokenError::InvalidAccountVersion.into()));
+        let mut longer = data.clone();
+        longer.push(0);
+        assert_eq!(loaded::<Auction>(longer, program_id, &program_id), Err(TokenError::InvalidAccountType.into()));
+        assert_eq!(loaded::<Auction>(data[..Auction::LEN - 1].to_vec(), program_id, &program_id), Err(TokenError::InvalidAccountType.into()));
+    }
+}
@@ -0,0 +1,108 @@
+//! Runtime stand-ins for unit tests of the program
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::{deserialize, ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+
+/// Account handed to the program under test
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+impl TestAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, lamports: u64, data: Vec<u8>) -> Self {
+        Self{
+            key,
+            owner,
+            lamports,
+            data,
+            is_signer: false,
+            is_writable: true,
+        }
+    }
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+    pub fn readonly(mut self) -> Self {
+        self.is_writable = false;
+        self
+    }
+}
+
+/// Serializes accounts into the program input layout and hands back their infos.
+///
+/// The buffer is leaked so the infos live for the whole test, and keeps the
+/// realloc padding and length prefixes the runtime provides.
+pub fn account_infos(accounts: &[TestAccount]) -> Vec<AccountInfo<'static>> {
+    let mut input: Vec<u8> = Vec::new();
+    input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+    for account in accounts {
+        input.push(NON_DUP_MARKER);
+        input.push(account.is_signer as u8);
+        input.push(account.is_writable as u8);
+        input.push(0);
+        input.extend_from_slice(&[0; 4]);
+        input.extend_from_slice(account.key.as_ref());
+        input.extend_from_slice(account.owner.as_ref());
+        input.extend_from_slice(&account.lamports.to_le_bytes());
+        input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&account.data);
+        input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        input.resize(input.len() + (BPF_ALIGN_OF_U128 - input.len() % BPF_ALIGN_OF_U128) % BPF_ALIGN_OF_U128, 0);
+        input.extend_from_slice(&0u64.to_le_bytes());
+    }
+    input.extend_from_slice(&0u64.to_le_bytes());
+    input.extend_from_slice(Pubkey::default().as_ref());
+    // u128 backing keeps the buffer aligned like the runtime input region
+    let words: &'static mut [u128] = Box::leak(vec![0u128; input.len() / 16 + 1].into_boxed_slice());
+    let base = words.as_mut_ptr() as *mut u8;
+    unsafe {
+        std::ptr::copy_nonoverlapping(input.as_ptr(), base, input.len());
+        deserialize(base).1
+    }
+}
+
+/// Installs the syscall stubs, rent is the default rent and only system transfers can be invoked
+pub fn install_stubs() {
+    set_syscall_stubs(Box::new(TestStubs));
+}
+
+struct TestStubs;
+impl SyscallStubs for TestStubs {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        // SystemInstruction::Transfer is tag 2 followed by the lamports
+        if instruction.program_id != system_program::id() || instruction.data.len() != 12 || instruction.data[..4] != 2u32.to_le_bytes()
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut amount = [0; 8];
+        amount.copy_from_slice(&instruction.data[4..]);
+        let amount = u64::from_le_bytes(amount);
+        let find = |key: &Pubkey| account_infos.iter().find(|info| info.key == key).ok_or(ProgramError::NotEnoughAccountKeys);
+        let from = find(&instruction.accounts[0].pubkey)?;
+        let to = find(&instruction.accounts[1].pubkey)?;
+        **from.try_borrow_mut_lamports()? = from.lamports().checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+        **to.try_borrow_mut_lamports()? += amount;
+        Ok(())
+    }
+}
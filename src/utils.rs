@@ -3,9 +3,25 @@ use solana_program::{
     account_info::{AccountInfo},
     system_instruction,
     program::{invoke_signed,invoke},
+    keccak::hashv,
     entrypoint::ProgramResult,
     program_error::ProgramError,
-    program_pack::Pack
+    program_pack::Pack,
+    system_program,
+    bpf_loader_upgradeable,
+    sysvar::{self,Sysvar,clock::Clock,rent::Rent},
+    msg,
+};
+use std::convert::TryFrom;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions,StateWithExtensions,transfer_hook::{self,TransferHookAccount}},
+    onchain::invoke_transfer_checked,
+    state::{Account,Mint},
+};
+use crate::{
+    error::TokenError,
+    AUCTIONPREFIX,
 };
 
 
@@ -112,4 +128,338 @@ pub fn create_pda_account<'a>(
         let amount = array_ref![data, 64, 8];
         Ok(u64::from_le_bytes(*amount))
     }
-    
\ No newline at end of file
+    /// Decimals and supply of a mint of either token program, extensions included
+    pub fn get_mint_state(mint: &AccountInfo) -> Result<(u8, u64), ProgramError> {
+        let data = mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        Ok((mint.base.decimals, mint.base.supply))
+    }
+    /// Mint authority of a mint of either token program
+    pub fn get_mint_authority(mint: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+        let data = mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        Ok(mint.base.mint_authority.into())
+    }
+    /// Transfer hook program of a mint, None for legacy mints and mints without a hook
+    pub fn get_transfer_hook_program(mint: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+        if *mint.owner != spl_token_2022::id() {
+            return Ok(None);
+        }
+        let data = mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        Ok(transfer_hook::get_program_id(&mint))
+    }
+    /// Checks program_id is the transfer hook program of the mint
+    pub fn assert_transfer_hook(mint: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+        if get_transfer_hook_program(mint)? != Some(*program_id) {
+            msg!("{} is not the transfer hook of {}", program_id, mint.key);
+            return Err(TokenError::InvalidTransferHook.into());
+        }
+        Ok(())
+    }
+    /// Checks Token-2022 is in the middle of a transfer out of token_account, so the hook isn't called directly
+    pub fn assert_transferring(token_account: &AccountInfo) -> ProgramResult {
+        let data = token_account.try_borrow_data()?;
+        let account = StateWithExtensions::<Account>::unpack(&data)?;
+        let transferring = account
+            .get_extension::<TransferHookAccount>()
+            .map(|extension| bool::from(extension.transferring))
+            .unwrap_or(false);
+        if !transferring {
+            msg!("{} is not being transferred", token_account.key);
+            return Err(TokenError::InvalidTransferHook.into());
+        }
+        Ok(())
+    }
+    /// Transfers tokens with transfer_checked, which mints with a transfer fee require.
+    /// The extra accounts of a transfer hook are looked up in additional_accounts.
+    /// An empty signer_seeds transfers out of a signing wallet
+    #[allow(clippy::too_many_arguments)]
+    pub fn transfer_tokens<'a>(
+        token_program: &AccountInfo<'a>,
+        source: &AccountInfo<'a>,
+        mint: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        additional_accounts: &[AccountInfo<'a>],
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let (decimals, _supply) = get_mint_state(mint)?;
+        invoke_transfer_checked(
+            token_program.key,
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            additional_accounts,
+            amount,
+            decimals,
+            signer_seeds,
+        )
+    }
+        pub fn generate_auction_pda(
+        nft_owner: &Pubkey,
+        pda: &Pubkey,
+        day: u64,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                AUCTIONPREFIX.as_bytes(),
+                &nft_owner.to_bytes(),
+                &pda.to_bytes(),
+                day.to_string().as_bytes(),
+            ],
+            program_id,
+        )
+    }
+    /// Checks leaf is in the merkle tree of root, every pair of nodes is hashed in sorted order
+    pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+        let computed = proof.iter().fold(leaf, |node, sibling| {
+            if node <= *sibling {
+                hashv(&[&node, sibling]).to_bytes()
+            } else {
+                hashv(&[sibling, &node]).to_bytes()
+            }
+        });
+        computed == *root
+    }
+    /// Allowlist leaf of a buyer and the fractions it may buy
+    pub fn allowlist_leaf(buyer: &Pubkey, cap: u64) -> [u8; 32] {
+        hashv(&[&buyer.to_bytes(), &cap.to_le_bytes()]).to_bytes()
+    }
+    pub fn assert_signer(account: &AccountInfo) -> ProgramResult {
+        if !account.is_signer {
+            msg!("{} must sign the transaction", account.key);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
+    pub fn assert_writable(account: &AccountInfo) -> ProgramResult {
+        if !account.is_writable {
+            msg!("{} must be writable", account.key);
+            return Err(TokenError::AccountNotWritable.into());
+        }
+        Ok(())
+    }
+    pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+        if account.owner != owner {
+            msg!("{} is not owned by {}", account.key, owner);
+            return Err(ProgramError::IllegalOwner);
+        }
+        Ok(())
+    }
+    /// Checks the account is the token program the vault was created with
+    pub fn assert_token_program(account: &AccountInfo, token_program: &Pubkey) -> ProgramResult {
+        if account.key != token_program {
+            msg!("{} is not the token program {}", account.key, token_program);
+            return Err(TokenError::InvalidTokenProgram.into());
+        }
+        Ok(())
+    }
+    /// Checks the account is spl_token or spl_token_2022
+    pub fn assert_supported_token_program(account: &AccountInfo) -> ProgramResult {
+        if *account.key != spl_token::id() && *account.key != spl_token_2022::id() {
+            msg!("{} is not a supported token program", account.key);
+            return Err(TokenError::InvalidTokenProgram.into());
+        }
+        Ok(())
+    }
+    pub fn assert_associated_token_program(account: &AccountInfo) -> ProgramResult {
+        if *account.key != spl_associated_token_account::id() {
+            msg!("{} is not the associated token program", account.key);
+            return Err(TokenError::InvalidAssociatedTokenProgram.into());
+        }
+        Ok(())
+    }
+    pub fn assert_system_program(account: &AccountInfo) -> ProgramResult {
+        if *account.key != system_program::id() {
+            msg!("{} is not the system program", account.key);
+            return Err(TokenError::InvalidSystemProgram.into());
+        }
+        Ok(())
+    }
+    pub fn assert_rent_sysvar(account: &AccountInfo) -> ProgramResult {
+        if *account.key != sysvar::rent::id() {
+            msg!("{} is not the rent sysvar", account.key);
+            return Err(TokenError::InvalidRentSysvar.into());
+        }
+        Ok(())
+    }
+    /// Checks the account is the pda derived from prefix, sender and pda and returns its bump seed
+    pub fn assert_pda(
+        account: &AccountInfo,
+        prefix: &str,
+        sender: &Pubkey,
+        pda: &Pubkey,
+        program_id: &Pubkey,
+        error: TokenError,
+    ) -> Result<u8, ProgramError> {
+        let (address, bump_seed) = generate_pda_and_bump_seed(prefix, sender, pda, program_id);
+        if address != *account.key {
+            msg!("{} is not the {} pda", account.key, prefix);
+            return Err(error.into());
+        }
+        Ok(bump_seed)
+    }
+    pub fn assert_associated_token(account: &AccountInfo, wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> ProgramResult {
+        if get_associated_token_address_with_program_id(wallet, mint, token_program) != *account.key {
+            msg!("{} is not the token account of {} for {}", account.key, wallet, mint);
+            return Err(TokenError::InvalidTokenAccount.into());
+        }
+        Ok(())
+    }
+    /// Current unix timestamp, rejecting clocks before the epoch
+    pub fn current_timestamp() -> Result<u64, ProgramError> {
+        let now = Clock::get()?.unix_timestamp;
+        u64::try_from(now).map_err(|_| TokenError::InvalidClock.into())
+    }
+    /// Seconds elapsed since create_at, rejecting clocks earlier than create_at
+    pub fn seconds_since(now: u64, create_at: u64) -> Result<u64, ProgramError> {
+        now.checked_sub(create_at).ok_or_else(|| {
+            msg!("Clock {} is earlier than creation time {}", now, create_at);
+            TokenError::InvalidClock.into()
+        })
+    }
+    pub fn assert_rent_exempt(account: &AccountInfo, rent: &Rent) -> ProgramResult {
+        if !rent.is_exempt(account.lamports(), account.data_len()) {
+            msg!("{} is not rent exempt", account.key);
+            return Err(TokenError::NotRentExempt.into());
+        }
+        Ok(())
+    }
+    /// Checks a pda paying out lamports keeps its rent-exempt minimum afterwards
+    pub fn assert_withdrawal_keeps_rent(account: &AccountInfo, amount: u64, rent: &Rent) -> ProgramResult {
+        let remaining = account.lamports().checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+        if remaining < rent.minimum_balance(account.data_len()) {
+            msg!("Withdrawing {} would leave {} below rent exemption", amount, account.key);
+            return Err(TokenError::NotRentExempt.into());
+        }
+        Ok(())
+    }
+    /// Reads the upgrade authority out of the program data account of program_id
+    pub fn get_upgrade_authority(program_data: &AccountInfo, program_id: &Pubkey) -> Result<Option<Pubkey>, ProgramError> {
+        let (program_data_address, _bump) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        if *program_data.key != program_data_address {
+            msg!("{} is not the program data account", program_data.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        assert_owned_by(program_data, &bpf_loader_upgradeable::id())?;
+        let data = program_data.try_borrow_data()?;
+        // bincode layout of UpgradeableLoaderState::ProgramData: u32 tag, u64 slot, Option<Pubkey>
+        check_data_len(&data, 45)?;
+        if u32::from_le_bytes(*array_ref![data, 0, 4]) != 3 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if data[12] == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Pubkey::new_from_array(*array_ref![data, 13, 32])))
+    }
+    /// Transfers lamports out of a signing wallet, zero amounts are skipped
+    pub fn transfer_lamports<'a>(
+        from: &AccountInfo<'a>,
+        to: &AccountInfo<'a>,
+        amount: u64,
+        system_program: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        if amount == 0 {
+            return Ok(());
+        }
+        invoke(
+            &system_instruction::transfer(from.key, to.key, amount),
+            &[from.clone(), to.clone(), system_program.clone()],
+        )
+    }
+    /// Transfers lamports out of a system owned pda, zero amounts are skipped
+    pub fn transfer_lamports_signed<'a>(
+        from: &AccountInfo<'a>,
+        to: &AccountInfo<'a>,
+        amount: u64,
+        system_program: &AccountInfo<'a>,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        if amount == 0 {
+            return Ok(());
+        }
+        invoke_signed(
+            &system_instruction::transfer(from.key, to.key, amount),
+            &[from.clone(), to.clone(), system_program.clone()],
+            &[seeds],
+        )
+    }
+    /// Moves lamports out of a program owned account, which keeps its rent-exempt minimum
+    pub fn transfer_program_lamports(
+        from: &AccountInfo,
+        to: &AccountInfo,
+        amount: u64,
+        rent: &Rent,
+    ) -> ProgramResult {
+        assert_withdrawal_keeps_rent(from, amount, rent)?;
+        let from_lamports = from.lamports().checked_sub(amount).ok_or(TokenError::Overflow)?;
+        let to_lamports = to.lamports().checked_add(amount).ok_or(TokenError::Overflow)?;
+        **from.try_borrow_mut_lamports()? = from_lamports;
+        **to.try_borrow_mut_lamports()? = to_lamports;
+        Ok(())
+    }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::keccak::Hash;
+
+    fn parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        if left <= right {
+            hashv(&[left, right]).to_bytes()
+        } else {
+            hashv(&[right, left]).to_bytes()
+        }
+    }
+
+    /// Buyers and their caps, the root and the proof of every leaf
+    type Allowlist = (Vec<(Pubkey, u64)>, [u8; 32], Vec<Vec<[u8; 32]>>);
+
+    /// Allowlist of four buyers with caps of 100 to 400
+    fn allowlist() -> Allowlist {
+        let buyers: Vec<(Pubkey, u64)> = (1..=4u8).map(|n| (Pubkey::new_from_array([n; 32]), n as u64 * 100)).collect();
+        let leaves: Vec<[u8; 32]> = buyers.iter().map(|(buyer, cap)| allowlist_leaf(buyer, *cap)).collect();
+        let left = parent(&leaves[0], &leaves[1]);
+        let right = parent(&leaves[2], &leaves[3]);
+        let proofs = vec![
+            vec![leaves[1], right],
+            vec![leaves[0], right],
+            vec![leaves[3], left],
+            vec![leaves[2], left],
+        ];
+        (buyers, parent(&left, &right), proofs)
+    }
+
+    #[test]
+    fn merkle_proof_accepts_every_allowlisted_buyer() {
+        let (buyers, root, proofs) = allowlist();
+        // pinned so the leaf and pair hashing keep matching the trees built off chain
+        assert_eq!(Hash::new_from_array(root).to_string(), "5jeBDCb7wT7zNfY5ysinHLdBZCS4WDnDLSSLLe2wZfZp");
+        for ((buyer, cap), proof) in buyers.iter().zip(&proofs) {
+            assert!(verify_merkle_proof(proof, &root, allowlist_leaf(buyer, *cap)));
+        }
+    }
+
+    #[test]
+    fn merkle_proof_rejects_tampered_claims() {
+        let (buyers, root, proofs) = allowlist();
+        let (buyer, cap) = buyers[2];
+        // a higher cap than the allowlist grants
+        assert!(!verify_merkle_proof(&proofs[2], &root, allowlist_leaf(&buyer, cap + 1)));
+        // another wallet reusing the proof
+        assert!(!verify_merkle_proof(&proofs[2], &root, allowlist_leaf(&Pubkey::new_unique(), cap)));
+        // a proof of another leaf
+        assert!(!verify_merkle_proof(&proofs[0], &root, allowlist_leaf(&buyer, cap)));
+        // a flipped bit in a sibling
+        let mut tampered = proofs[2].clone();
+        tampered[1][0] ^= 1;
+        assert!(!verify_merkle_proof(&tampered, &root, allowlist_leaf(&buyer, cap)));
+        // a truncated proof
+        assert!(!verify_merkle_proof(&proofs[2][..1], &root, allowlist_leaf(&buyer, cap)));
+    }
+}